@@ -0,0 +1,361 @@
+use std::collections::BTreeMap;
+
+use chrono::{Duration, NaiveDate};
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Serialize};
+
+use crate::events::{self, Event};
+use crate::period::Period;
+use crate::util;
+
+/// Rates and rounding for `hours bill`, from the settings.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Billing {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+    /// The hourly rate for time without a project, or on a project without a rate of its own.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_rate: Option<Decimal>,
+    /// Hourly rates per project.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub rates: BTreeMap<String, Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rounding: Option<Rounding>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct Rounding {
+    /// Whether each session or each day's time per project is rounded.
+    pub per: RoundingUnit,
+    pub direction: RoundingDirection,
+    /// What to round to, like 6, 15 or 30 minutes.
+    pub minutes: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RoundingUnit {
+    Day,
+    Session,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RoundingDirection {
+    Up,
+    Down,
+    Nearest,
+}
+
+impl Rounding {
+    pub fn apply(&self, duration: Duration) -> Duration {
+        let unit = i64::from(self.minutes.max(1)) * 60;
+        let seconds = duration.num_seconds();
+        let units = match self.direction {
+            RoundingDirection::Up => {
+                seconds.div_euclid(unit) + (seconds.rem_euclid(unit) > 0) as i64
+            }
+            RoundingDirection::Down => seconds.div_euclid(unit),
+            RoundingDirection::Nearest => (seconds + unit / 2).div_euclid(unit),
+        };
+        Duration::seconds(units * unit)
+    }
+}
+
+/// The time on a project in the billing summary.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Line {
+    pub project: Option<String>,
+    pub billable: bool,
+    pub worked: Duration,
+    pub billed: Duration,
+    pub rate: Option<Decimal>,
+    /// `None` if there's no rate for the project.
+    pub amount: Option<Decimal>,
+}
+
+impl Billing {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    fn rate(&self, project: Option<&String>) -> Option<Decimal> {
+        project
+            .and_then(|project| self.rates.get(project))
+            .copied()
+            .or(self.default_rate)
+    }
+
+    /// The worked and billed time per project in `period`. Time added outside of sessions, like
+    /// imported hours, is billed as time without a project.
+    pub fn summary(&self, events: &[Event], period: &Period) -> Vec<Line> {
+        // Pieces of time that are rounded on their own.
+        let mut pieces: BTreeMap<(Option<String>, bool, NaiveDate), Vec<Duration>> =
+            BTreeMap::new();
+        let per_session = self.rounding.map(|r| r.per) == Some(RoundingUnit::Session);
+        for session in events::sessions(events).iter().filter(|s| !s.open) {
+            let date = session.date();
+            if !period.contains(date) {
+                continue;
+            }
+            let key = (session.project.clone(), session.billable, date);
+            let durations = pieces.entry(key).or_default();
+            match durations.last_mut() {
+                Some(total) if !per_session => *total = *total + session.duration(),
+                _ => durations.push(session.duration()),
+            }
+        }
+        for event in events {
+            if let Event::Adjusted { date, seconds, .. } = event {
+                let date = *date;
+                if period.contains(date) {
+                    let durations = pieces.entry((None, true, date)).or_default();
+                    match durations.last_mut() {
+                        Some(total) if !per_session => {
+                            *total = *total + Duration::seconds(*seconds)
+                        }
+                        _ => durations.push(Duration::seconds(*seconds)),
+                    }
+                }
+            }
+        }
+
+        let mut lines: BTreeMap<(Option<String>, bool), (Duration, Duration)> = BTreeMap::new();
+        for ((project, billable, _), durations) in pieces {
+            let (worked, billed) = lines
+                .entry((project, billable))
+                .or_insert((Duration::zero(), Duration::zero()));
+            for duration in durations {
+                *worked = *worked + duration;
+                if billable {
+                    let rounded = self.rounding.map_or(duration, |r| r.apply(duration));
+                    *billed = *billed + rounded;
+                }
+            }
+        }
+        lines
+            .into_iter()
+            .map(|((project, billable), (worked, billed))| {
+                let rate = self.rate(project.as_ref()).filter(|_| billable);
+                Line {
+                    amount: rate.map(|rate| amount(billed, rate)),
+                    project,
+                    billable,
+                    worked,
+                    billed,
+                    rate,
+                }
+            })
+            .collect()
+    }
+
+    /// The summary as a table, with totals.
+    pub fn render(&self, lines: &[Line], period: &Period) -> String {
+        let mut output = vec![format!("Billing for {}", period.name)];
+        if let Some(rounding) = self.rounding {
+            output[0].push_str(&format!(
+                ", each {} rounded {} {} minutes",
+                match rounding.per {
+                    RoundingUnit::Day => "day",
+                    RoundingUnit::Session => "session",
+                },
+                match rounding.direction {
+                    RoundingDirection::Up => "up to",
+                    RoundingDirection::Down => "down to",
+                    RoundingDirection::Nearest => "to the nearest",
+                },
+                rounding.minutes
+            ));
+        }
+        output.push(String::new());
+        output.push(format!(
+            "{:<24} {:>10} {:>10} {:>8} {:>12}",
+            "Project", "Worked", "Billed", "Rate", "Amount"
+        ));
+        let (mut worked, mut billed, mut total) =
+            (Duration::zero(), Duration::zero(), Decimal::ZERO);
+        for line in lines {
+            let mut name = line
+                .project
+                .clone()
+                .unwrap_or_else(|| "(no project)".to_owned());
+            if !line.billable {
+                name.push_str(" (non-billable)");
+            }
+            worked = worked + line.worked;
+            billed = billed + line.billed;
+            total += line.amount.unwrap_or_default();
+            output.push(format!(
+                "{:<24} {:>10} {:>10} {:>8} {:>12}",
+                name,
+                util::format_duration(line.worked),
+                util::format_duration(line.billed),
+                line.rate
+                    .map_or("-".to_owned(), |rate| format!("{:.2}", rate)),
+                line.amount
+                    .map_or("-".to_owned(), |amount| format!("{:.2}", amount))
+            ));
+        }
+        output.push(format!(
+            "{:<24} {:>10} {:>10} {:>8} {:>12}",
+            "Total",
+            util::format_duration(worked),
+            util::format_duration(billed),
+            "",
+            format!("{:.2}", total)
+        ));
+        if let Some(currency) = &self.currency {
+            let last = output.last_mut().unwrap();
+            last.push(' ');
+            last.push_str(currency);
+        }
+        if lines
+            .iter()
+            .any(|line| line.billable && line.rate.is_none())
+        {
+            output.push(String::new());
+            output.push("Projects without a rate aren't included in the total.".to_owned());
+        }
+        output.join("\n")
+    }
+}
+
+/// The amount for `billed` time at an hourly `rate`, rounded to cents.
+fn amount(billed: Duration, rate: Decimal) -> Decimal {
+    let hours = Decimal::from(billed.num_seconds()) / Decimal::from(3600);
+    (hours * rate).round_dp_with_strategy(2, RoundingStrategy::MidpointAwayFromZero)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use chrono::{FixedOffset, NaiveDate, TimeZone};
+
+    use super::*;
+
+    fn decimal(s: &str) -> Decimal {
+        Decimal::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn test_rounding() {
+        let cases = [
+            (RoundingDirection::Up, 15, 61, 75),
+            (RoundingDirection::Up, 15, 60, 60),
+            (RoundingDirection::Down, 30, 89, 60),
+            (RoundingDirection::Nearest, 6, 62, 60),
+            (RoundingDirection::Nearest, 6, 63, 66),
+        ];
+
+        for &(direction, minutes, duration, expected) in cases.iter() {
+            let rounding = Rounding {
+                per: RoundingUnit::Day,
+                direction,
+                minutes,
+            };
+            assert_eq!(
+                rounding.apply(Duration::minutes(duration)),
+                Duration::minutes(expected)
+            );
+        }
+    }
+
+    #[test]
+    fn test_summary() {
+        let at = |day, hour, min| {
+            FixedOffset::east(2 * 3600)
+                .ymd(2026, 9, day)
+                .and_hms(hour, min, 0)
+        };
+        let session = |day, from: (u32, u32), to: (u32, u32), project: &str, billable| {
+            vec![
+                Event::SessionStarted {
+                    at: at(day, from.0, from.1),
+                    project: Some(project.to_owned()),
+                    billable,
+                },
+                Event::Stopped {
+                    at: at(day, to.0, to.1),
+                },
+            ]
+        };
+        let mut events = Vec::new();
+        events.extend(session(1, (9, 0), (10, 5), "acme", true));
+        events.extend(session(1, (11, 0), (11, 20), "acme", true));
+        events.extend(session(2, (9, 0), (10, 0), "internal", false));
+        events.push(Event::Adjusted {
+            date: NaiveDate::from_ymd(2026, 9, 3),
+            seconds: 3600,
+            reason: "imported".to_owned(),
+        });
+        let mut billing = Billing {
+            currency: Some("EUR".to_owned()),
+            default_rate: Some(decimal("80")),
+            rates: vec![("acme".to_owned(), decimal("95.50"))]
+                .into_iter()
+                .collect(),
+            rounding: Some(Rounding {
+                per: RoundingUnit::Session,
+                direction: RoundingDirection::Up,
+                minutes: 15,
+            }),
+        };
+        let september = Period::month(2026, 9);
+
+        let lines = billing.summary(&events, &september);
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].project, None);
+        assert_eq!(lines[0].amount, Some(decimal("80.00")));
+        assert_eq!(lines[1].project.as_deref(), Some("acme"));
+        assert_eq!(lines[1].worked, Duration::minutes(85));
+        // 01:15:00 + 00:30:00
+        assert_eq!(lines[1].billed, Duration::minutes(105));
+        assert_eq!(lines[1].amount, Some(decimal("167.13")));
+        assert_eq!(lines[2].project.as_deref(), Some("internal"));
+        assert_eq!(lines[2].billed, Duration::zero());
+        assert_eq!(lines[2].amount, None);
+
+        billing.rounding.as_mut().unwrap().per = RoundingUnit::Day;
+        let lines = billing.summary(&events, &september);
+        // 01:25:00 rounded up once.
+        assert_eq!(lines[1].billed, Duration::minutes(90));
+    }
+
+    #[test]
+    fn test_counted_suspend_billed_with_session() {
+        let at = |hour| {
+            FixedOffset::east(2 * 3600)
+                .ymd(2026, 9, 1)
+                .and_hms(hour, 0, 0)
+        };
+        let events = vec![
+            Event::SessionStarted {
+                at: at(9),
+                project: Some("internal".to_owned()),
+                billable: false,
+            },
+            Event::Paused { at: at(10) },
+            Event::Resumed { at: at(12) },
+            Event::SuspendCounted {
+                from: at(10),
+                to: at(12),
+            },
+            Event::Stopped { at: at(13) },
+        ];
+        let billing = Billing {
+            default_rate: Some(decimal("80")),
+            ..Billing::default()
+        };
+
+        let lines = billing.summary(&events, &Period::month(2026, 9));
+
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].project.as_deref(), Some("internal"));
+        assert_eq!(lines[0].worked, Duration::hours(4));
+        assert_eq!(lines[0].billed, Duration::zero());
+        assert_eq!(lines[0].amount, None);
+    }
+}
@@ -0,0 +1,269 @@
+use chrono::{Datelike, Duration, NaiveDate};
+use crossterm::style::{self, Color};
+
+use crate::period::Period;
+use crate::remaining_work;
+use crate::timesheet::Timesheet;
+use crate::util;
+
+/// How many weeks the heatmap shows.
+const HEATMAP_WEEKS: i64 = 26;
+/// How many weeks the bar chart shows.
+const BAR_CHART_WEEKS: i64 = 8;
+const BAR_WIDTH: i64 = 40;
+
+/// The heatmap's levels, from no hours to a long day, as they look without and with colors.
+const LEVELS: [(&str, Color); 5] = [
+    ("·", Color::DarkGrey),
+    ("░", Color::AnsiValue(22)),
+    ("▒", Color::AnsiValue(28)),
+    ("▓", Color::AnsiValue(34)),
+    ("█", Color::AnsiValue(40)),
+];
+const SPARKS: [&str; 8] = ["▁", "▂", "▃", "▄", "▅", "▆", "▇", "█"];
+
+/// Draws charts of a timesheet as text, with colors only if `colors` is set.
+pub struct Charts<'a> {
+    timesheet: &'a Timesheet,
+    today: NaiveDate,
+    colors: bool,
+}
+
+impl<'a> Charts<'a> {
+    pub fn new(timesheet: &'a Timesheet, today: NaiveDate, colors: bool) -> Self {
+        Self {
+            timesheet,
+            today,
+            colors,
+        }
+    }
+
+    /// A calendar of the last weeks with a row per weekday, like GitHub's contribution graph.
+    pub fn heatmap(&self) -> String {
+        let this_week = Period::week(self.today);
+        let first_monday = this_week.from - Duration::weeks(HEATMAP_WEEKS - 1);
+        let mondays: Vec<_> = (0..HEATMAP_WEEKS)
+            .map(|week| first_monday + Duration::weeks(week))
+            .collect();
+
+        // Month names above the week their first day is in.
+        let mut header = String::new();
+        for (i, monday) in mondays.iter().enumerate() {
+            let column = 4 + i * 2;
+            if header.chars().count() > column {
+                continue;
+            }
+            let sunday = *monday + Duration::days(6);
+            if i == 0 || sunday.day() <= 7 {
+                header.push_str(&" ".repeat(column - header.chars().count()));
+                header.push_str(&sunday.format("%b").to_string());
+            }
+        }
+
+        let mut lines = vec![header.trim_end().to_owned()];
+        for (day, weekday) in ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"]
+            .iter()
+            .enumerate()
+        {
+            let mut line = format!("{} ", weekday);
+            for monday in &mondays {
+                let date = *monday + Duration::days(day as i64);
+                if date > self.today {
+                    break;
+                }
+                let (symbol, color) = LEVELS[level(self.timesheet.get_hours(&date))];
+                line.push_str(&self.paint(symbol, color));
+                line.push(' ');
+            }
+            lines.push(line.trim_end().to_owned());
+        }
+        let legend: String = LEVELS
+            .iter()
+            .map(|&(symbol, color)| self.paint(symbol, color))
+            .collect::<Vec<_>>()
+            .join(" ");
+        lines.push(format!("    less {} more", legend));
+        lines.join("\n")
+    }
+
+    /// A bar per week with its total, green if it reached the week's target.
+    pub fn weekly_bars(&self) -> String {
+        let weeks: Vec<_> = (0..BAR_CHART_WEEKS)
+            .rev()
+            .map(|weeks_ago| Period::week(self.today - Duration::weeks(weeks_ago)))
+            .collect();
+        let totals: Vec<_> = weeks
+            .iter()
+            .map(|week| week.hours(self.timesheet))
+            .collect();
+        // Weeks with negative hours, from corrections, get no bar.
+        let longest = totals
+            .iter()
+            .map(|total| (*total).max(Duration::zero()))
+            .collect::<Vec<_>>()
+            .iter()
+            .chain(
+                weeks
+                    .iter()
+                    .map(|week| week.target_until(week.to))
+                    .collect::<Vec<_>>()
+                    .iter(),
+            )
+            .max()
+            .copied()
+            .filter(|longest| *longest > Duration::zero())
+            .unwrap_or_else(|| Duration::hours(1));
+        let mut lines = Vec::new();
+        for (week, total) in weeks.iter().zip(totals) {
+            let width = (total.num_seconds() * BAR_WIDTH / longest.num_seconds()).max(0);
+            let target = week.target_until(self.today.min(week.to));
+            let color = if total >= target {
+                Color::Green
+            } else {
+                Color::Yellow
+            };
+            lines.push(format!(
+                "W{:0>2} {:<width$} {}",
+                week.from.iso_week().week(),
+                self.paint(&"█".repeat(width as usize), color),
+                util::format_duration(total),
+                // The padding has to account for the invisible color codes.
+                width = BAR_WIDTH as usize + self.paint("", color).len(),
+            ));
+        }
+        lines.join("\n")
+    }
+
+    /// A spark per day of this month, green on working days that reached the daily target, red
+    /// on those that didn't, followed by the month's total and target.
+    pub fn month_sparkline(&self) -> String {
+        let month = Period::month(self.today.year(), self.today.month());
+        let daily_target = month.target_until(month.to) / month.working_days_until(month.to);
+        let mut sparkline = String::new();
+        for date in month.days().filter(|date| *date <= self.today) {
+            let hours = self.timesheet.get_hours(&date);
+            let spark = if hours <= Duration::zero() {
+                " "
+            } else {
+                let index = hours.num_minutes() * SPARKS.len() as i64 / (10 * 60);
+                SPARKS[index.clamp(0, SPARKS.len() as i64 - 1) as usize]
+            };
+            let color = if !remaining_work::is_working_day(date.weekday()) {
+                Color::DarkGrey
+            } else if hours >= daily_target {
+                Color::Green
+            } else {
+                Color::Red
+            };
+            sparkline.push_str(&self.paint(spark, color));
+        }
+        format!(
+            "{} |{}|\n{} of {} target, {} so far",
+            month.name,
+            sparkline,
+            util::format_duration(month.hours(self.timesheet)),
+            util::format_duration(month.target_until(month.to)),
+            util::format_duration(month.target_until(self.today))
+        )
+    }
+
+    fn paint(&self, text: &str, color: Color) -> String {
+        if self.colors {
+            style::style(text).with(color).to_string()
+        } else {
+            text.to_owned()
+        }
+    }
+}
+
+/// The heatmap level for a day's hours.
+fn level(hours: Duration) -> usize {
+    match hours.num_minutes() {
+        minutes if minutes <= 0 => 0,
+        minutes if minutes < 4 * 60 => 1,
+        minutes if minutes < 7 * 60 => 2,
+        minutes if minutes < 9 * 60 => 3,
+        _ => 4,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::report::Report;
+
+    use super::*;
+
+    fn sample_timesheet() -> Timesheet {
+        Timesheet::parse_report(&Report(
+            "
+01.09.2026 08:00:00
+02.09.2026 03:00:00
+03.09.2026 10:00:00
+07.09.2026 05:00:00
+"
+            .to_owned(),
+        ))
+    }
+
+    #[test]
+    fn test_heatmap() {
+        let timesheet = sample_timesheet();
+        let charts = Charts::new(&timesheet, NaiveDate::from_ymd(2026, 9, 8), false);
+
+        let heatmap = charts.heatmap();
+
+        let lines: Vec<_> = heatmap.lines().collect();
+        assert_eq!(lines.len(), 9);
+        assert!(lines[0].ends_with("Sep"));
+        assert!(lines[1].starts_with("Mon · · "));
+        assert!(lines[1].ends_with("· ▒"));
+        assert!(lines[2].ends_with("▓ ·"));
+        assert!(lines[3].ends_with("░"));
+        assert!(lines[4].ends_with("█"));
+    }
+
+    #[test]
+    fn test_weekly_bars() {
+        let timesheet = sample_timesheet();
+        let charts = Charts::new(&timesheet, NaiveDate::from_ymd(2026, 9, 8), false);
+
+        let bars = charts.weekly_bars();
+
+        let lines: Vec<_> = bars.lines().collect();
+        assert_eq!(lines.len(), BAR_CHART_WEEKS as usize);
+        assert!(lines[6].starts_with("W36 ████"));
+        assert!(lines[6].ends_with(" 21:00:00"));
+        assert!(lines[7].starts_with("W37 ████"));
+        assert!(lines[7].ends_with(" 05:00:00"));
+    }
+
+    #[test]
+    fn test_month_sparkline() {
+        let timesheet = sample_timesheet();
+        let charts = Charts::new(&timesheet, NaiveDate::from_ymd(2026, 9, 8), false);
+
+        let sparkline = charts.month_sparkline();
+
+        assert_eq!(
+            sparkline,
+            "September 2026 |▇▃█   ▅ |\n26:00:00 of 160:00:00 target, 43:38:10 so far"
+        );
+    }
+
+    #[test]
+    fn test_negative_hours() {
+        let timesheet = Timesheet::parse_report(&Report(
+            "07.09.2026 -02:00:00\n08.09.2026 -01:00:00".to_owned(),
+        ));
+        let charts = Charts::new(&timesheet, NaiveDate::from_ymd(2026, 9, 8), false);
+
+        let bars = charts.weekly_bars();
+        let sparkline = charts.month_sparkline();
+
+        assert!(bars.lines().last().unwrap().starts_with("W37  "));
+        assert!(!bars.contains('█'));
+        assert!(sparkline.starts_with("September 2026 |        |"));
+        assert_eq!(level(Duration::hours(-2)), 0);
+        assert!(charts.heatmap().lines().nth(1).unwrap().ends_with("· ·"));
+    }
+}
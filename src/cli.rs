@@ -0,0 +1,366 @@
+use std::{
+    env,
+    path::{Path, PathBuf},
+    process,
+};
+
+use chrono::NaiveDate;
+
+use crate::clock;
+use crate::daemon::Request;
+use crate::export::Format;
+use crate::import::{self, Importer, Mode};
+use crate::leave::LeaveKind;
+use crate::paths;
+use crate::period::Period;
+
+pub struct Args {
+    /// Whether closed months may be changed.
+    pub force: bool,
+    pub command: Command,
+}
+
+pub enum Command {
+    Track {
+        pomodoro: bool,
+    },
+    Init,
+    EncryptKey,
+    Attach,
+    Daemon {
+        /// Whether to stay in the foreground instead of detaching from the terminal.
+        foreground: bool,
+    },
+    Control(Request),
+    Report(Period),
+    Bill(Period),
+    Pdf {
+        month: Period,
+        output: Option<PathBuf>,
+    },
+    Stats,
+    Close(Period),
+    Compliance(Period),
+    ListLeave,
+    /// Takes the working days of a period off, or with `None`, makes them normal days again.
+    Leave(Option<LeaveKind>, Period),
+    Export(Format, Option<Period>),
+    Import {
+        importer: Box<dyn Importer>,
+        file: PathBuf,
+        mode: Mode,
+        dry_run: bool,
+    },
+}
+
+const USAGE: &str = "Usage: hours [--config <file>] [<command>]
+
+Options:
+    --config <file>   Settings file to use, defaults to $HOURS_CONFIG or
+                      $XDG_CONFIG_HOME/hours.yaml
+    --force           Change the hours of closed months anyway, when editing the
+                      report, importing or syncing
+
+Commands:
+    (none)        Start the timer for today, syncing with the gist before and after
+    init          Create a private gist and write the settings file
+    encrypt-key   Move the API key into a file encrypted with a passphrase
+    pomodoro      Start the timer in Pomodoro mode, pausing it for breaks and ringing
+                  a bell when work or a break is over
+    attach        Show the timer of an hours process running in another terminal
+    daemon [--foreground]
+                  Track time in the background, controlled by the commands below;
+                  also started when the binary is invoked as `hoursd`. With
+                  --foreground, it doesn't detach from the terminal
+    start [--non-billable] [<project>]
+                        Start a session in the daemon
+    stop                Stop the daemon's session and sync with the gist
+    pause, resume       Pause or resume the daemon's session
+    status              Show the daemon's session
+    switch [--non-billable] <project>
+                        Stop the daemon's session and start one on another project
+    shutdown            Stop the daemon, booking its session if there is one
+    report [<period>]   Show the hours of a period, by default this month:
+                        --week [2026-W38], --month [2026-09], --quarter [2026-Q3],
+                        --year [2026] or --from 2026-09-01 --to 2026-09-30
+    bill [<period>]     Show the worked and billed hours per project and what they
+                        come to at the configured rates, by default this month
+    pdf [<month>] [--output <file>]
+                        Write the timesheet of a month, like 2026-09 and by default
+                        this one, to a PDF, by default hours-2026-09.pdf
+    compliance [<month>]
+                        Check a month, by default this one, against the break, daily
+                        maximum and rest rules
+    leave               List this year's days off
+    leave vacation|sick|holiday|comp <date> [<to>]
+                        Take a day, or the working days up to <to>, off
+    leave remove <date> [<to>]
+                        Make days off normal days again
+    close <month>       Close a month that's over, like 2026-09, so its hours
+                        can't change without --force
+    stats               Show charts and statistics of the hours worked
+    export csv|json|ical [<period>] [--delimiter <char>] [--decimal]
+                        Print the timesheet, or its sessions as calendar events;
+                        --delimiter and --decimal (decimal hours) are for CSV only
+    import toggl|clockify|timewarrior|csv <file> [--replace] [--dry-run]
+                        Add the hours exported from another time tracker, or with
+                        --replace, use them instead of the days' recorded hours;
+                        --dry-run only shows what would change";
+
+pub fn parse_args() -> Args {
+    parse(env::args(), paths::set_config_file, &clock::today)
+}
+
+/// Parses `argv`, passing `--config` to `set_config_file` before anything that may read the
+/// settings, like `today` for the default period.
+fn parse(
+    argv: impl Iterator<Item = String>,
+    set_config_file: impl FnOnce(PathBuf),
+    today: &dyn Fn() -> NaiveDate,
+) -> Args {
+    let mut config = None;
+    let mut force = false;
+    let mut args = Vec::new();
+    let mut iter = argv;
+    let invoked_as = iter.next().unwrap_or_default();
+    while let Some(arg) = iter.next() {
+        if arg == "--config" {
+            config = Some(
+                iter.next()
+                    .map(PathBuf::from)
+                    .unwrap_or_else(|| usage_error()),
+            );
+        } else if let Some(path) = arg.strip_prefix("--config=") {
+            config = Some(PathBuf::from(path));
+        } else if arg == "--force" {
+            force = true;
+        } else {
+            args.push(arg);
+        }
+    }
+    if let Some(config) = config {
+        set_config_file(config);
+    }
+    if Path::new(&invoked_as).file_name() == Some("hoursd".as_ref()) {
+        let foreground = match args.as_slice() {
+            [] => false,
+            [flag] if flag == "--foreground" => true,
+            _ => usage_error(),
+        };
+        return Args {
+            force,
+            command: Command::Daemon { foreground },
+        };
+    }
+    let command = match args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        [] => Command::Track { pomodoro: false },
+        ["pomodoro"] => Command::Track { pomodoro: true },
+        ["init"] => Command::Init,
+        ["encrypt-key"] => Command::EncryptKey,
+        ["attach"] => Command::Attach,
+        ["daemon"] => Command::Daemon { foreground: false },
+        ["daemon", "--foreground"] => Command::Daemon { foreground: true },
+        ["start"] => Command::Control(Request::Start {
+            project: None,
+            billable: true,
+        }),
+        ["start", "--non-billable"] => Command::Control(Request::Start {
+            project: None,
+            billable: false,
+        }),
+        ["start", "--non-billable", project] => Command::Control(Request::Start {
+            project: Some(project.to_string()),
+            billable: false,
+        }),
+        ["start", project] => Command::Control(Request::Start {
+            project: Some(project.to_string()),
+            billable: true,
+        }),
+        ["stop"] => Command::Control(Request::Stop),
+        ["pause"] => Command::Control(Request::Pause),
+        ["resume"] => Command::Control(Request::Resume),
+        ["status"] => Command::Control(Request::Status),
+        ["switch", "--non-billable", project] => Command::Control(Request::Switch {
+            project: project.to_string(),
+            billable: false,
+        }),
+        ["switch", project] => Command::Control(Request::Switch {
+            project: project.to_string(),
+            billable: true,
+        }),
+        ["shutdown"] => Command::Control(Request::Shutdown),
+        ["report", options @ ..] => Command::Report(parse_period_options(options, today)),
+        ["bill", options @ ..] => Command::Bill(parse_period_options(options, today)),
+        ["pdf", options @ ..] => parse_pdf(options, today),
+        ["stats"] => Command::Stats,
+        ["compliance"] => Command::Compliance(parse_period("--month", None, today)),
+        ["compliance", month] => Command::Compliance(parse_period("--month", Some(month), today)),
+        ["leave"] => Command::ListLeave,
+        ["leave", "remove", days @ ..] => Command::Leave(None, parse_days(days)),
+        ["leave", kind, days @ ..] => Command::Leave(
+            Some(LeaveKind::parse(kind).unwrap_or_else(|| usage_error())),
+            parse_days(days),
+        ),
+        ["close", month] => Command::Close(parse_period("--month", Some(month), today)),
+        ["export", format, options @ ..] => parse_export(format, options, today),
+        ["import", importer, file, options @ ..] => {
+            let mut mode = Mode::Merge;
+            let mut dry_run = false;
+            for &option in options {
+                match option {
+                    "--replace" => mode = Mode::Replace,
+                    "--dry-run" => dry_run = true,
+                    _ => usage_error(),
+                }
+            }
+            Command::Import {
+                importer: import::importer(importer).unwrap_or_else(|| usage_error()),
+                file: PathBuf::from(file),
+                mode,
+                dry_run,
+            }
+        }
+        ["-h"] | ["--help"] | ["help"] => {
+            println!("{}", USAGE);
+            process::exit(0);
+        }
+        _ => usage_error(),
+    };
+    Args { force, command }
+}
+
+fn parse_export(format: &str, options: &[&str], today: &dyn Fn() -> NaiveDate) -> Command {
+    let mut period = None;
+    let mut delimiter = None;
+    let mut decimal = false;
+    let mut from = None;
+    let mut to = None;
+    let mut options = options.iter().copied().peekable();
+    while let Some(option) = options.next() {
+        match option {
+            "--delimiter" => {
+                let value = options.next().unwrap_or_else(|| usage_error());
+                let mut chars = value.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => delimiter = Some(c),
+                    _ => usage_error(),
+                }
+            }
+            "--decimal" => decimal = true,
+            "--from" => from = Some(parse_date(options.next())),
+            "--to" => to = Some(parse_date(options.next())),
+            flag => {
+                let value = options.next_if(|value| !value.starts_with("--"));
+                period = Some(parse_period(flag, value, today));
+            }
+        }
+    }
+    match (from, to) {
+        (Some(from), Some(to)) if period.is_none() && from <= to => {
+            period = Some(Period::range(from, to))
+        }
+        (None, None) => {}
+        _ => usage_error(),
+    }
+    let format = match (format, delimiter, decimal) {
+        ("csv", delimiter, decimal) => Format::Csv {
+            delimiter: delimiter.unwrap_or(','),
+            decimal,
+        },
+        ("json", None, false) => Format::Json,
+        ("ical", None, false) => Format::ICalendar,
+        _ => usage_error(),
+    };
+    Command::Export(format, period)
+}
+
+fn parse_pdf(options: &[&str], today: &dyn Fn() -> NaiveDate) -> Command {
+    let (month, output) = match options {
+        [] => (None, None),
+        [month] => (Some(*month), None),
+        ["--output", output] => (None, Some(*output)),
+        [month, "--output", output] => (Some(*month), Some(*output)),
+        _ => usage_error(),
+    };
+    Command::Pdf {
+        month: parse_period("--month", month, today),
+        output: output.map(PathBuf::from),
+    }
+}
+
+/// The value of `--from` or `--to`, which has to be there and be a date.
+fn parse_date(value: Option<&str>) -> NaiveDate {
+    value
+        .and_then(Period::parse_date)
+        .unwrap_or_else(|| usage_error())
+}
+
+/// A day or, with two dates, the days from the first to the second.
+fn parse_days(days: &[&str]) -> Period {
+    let (from, to) = match days {
+        [day] => (Period::parse_date(day), Period::parse_date(day)),
+        [from, to] => (Period::parse_date(from), Period::parse_date(to)),
+        _ => usage_error(),
+    };
+    match (from, to) {
+        (Some(from), Some(to)) if from <= to => Period::range(from, to),
+        _ => usage_error(),
+    }
+}
+
+/// The period of `report` and `bill`, by default this month.
+fn parse_period_options(options: &[&str], today: &dyn Fn() -> NaiveDate) -> Period {
+    match options {
+        [] => parse_period("--month", None, today),
+        [flag] => parse_period(flag, None, today),
+        [flag, value] if *flag != "--from" => parse_period(flag, Some(value), today),
+        ["--from", from, "--to", to] => match (Period::parse_date(from), Period::parse_date(to)) {
+            (Some(from), Some(to)) if from <= to => Period::range(from, to),
+            _ => usage_error(),
+        },
+        _ => usage_error(),
+    }
+}
+
+fn parse_period(flag: &str, value: Option<&str>, today: &dyn Fn() -> NaiveDate) -> Period {
+    Period::parse(flag, value, today()).unwrap_or_else(|| usage_error())
+}
+
+fn usage_error() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+
+    #[test]
+    fn test_config_before_default_period() {
+        let config = RefCell::new(None);
+        let today = || {
+            // Reading the settings before `--config` is applied would use the default file.
+            assert_eq!(*config.borrow(), Some(PathBuf::from("/tmp/x/c.yaml")));
+            NaiveDate::from_ymd(2026, 10, 19)
+        };
+        let argv = ["hours", "--config", "/tmp/x/c.yaml", "report"];
+
+        let args = parse(
+            argv.iter().map(|arg| arg.to_string()),
+            |path| *config.borrow_mut() = Some(path),
+            &today,
+        );
+
+        match args.command {
+            Command::Report(period) => assert_eq!(period, Period::month(2026, 10)),
+            _ => panic!("not a report"),
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use std::sync::OnceLock;
+
+use chrono::{DateTime, FixedOffset, Local, NaiveDate};
+use chrono_tz::Tz;
+
+static HOME_ZONE: OnceLock<Option<Tz>> = OnceLock::new();
+
+/// The timezone whose calendar days hours are booked on, `timezone` from the settings. Without one
+/// it's the system's timezone.
+#[cfg(not(test))]
+fn home_zone() -> Option<Tz> {
+    *HOME_ZONE.get_or_init(|| {
+        crate::settings::Settings::try_load().and_then(|settings| settings.timezone)
+    })
+}
+
+/// Tests don't depend on the settings or the machine's timezone.
+#[cfg(test)]
+fn home_zone() -> Option<Tz> {
+    *HOME_ZONE.get_or_init(|| Some(chrono_tz::Europe::Berlin))
+}
+
+/// The current time with the system's UTC offset, as recorded in the event log.
+pub fn now() -> DateTime<FixedOffset> {
+    Local::now().into()
+}
+
+/// The day `at` falls on in the home timezone, wherever it was recorded.
+pub fn date(at: DateTime<FixedOffset>) -> NaiveDate {
+    match home_zone() {
+        Some(zone) => at.with_timezone(&zone).naive_local().date(),
+        None => at.with_timezone(&Local).naive_local().date(),
+    }
+}
+
+/// Today in the home timezone.
+pub fn today() -> NaiveDate {
+    date(now())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn test_date() {
+        let new_york = FixedOffset::west(4 * 3600);
+
+        // 20:00 in New York is already the next day at home in Berlin.
+        assert_eq!(
+            date(new_york.ymd(2026, 10, 19).and_hms(20, 0, 0)),
+            NaiveDate::from_ymd(2026, 10, 20)
+        );
+        assert_eq!(
+            date(new_york.ymd(2026, 10, 19).and_hms(17, 59, 0)),
+            NaiveDate::from_ymd(2026, 10, 19)
+        );
+    }
+}
@@ -0,0 +1,345 @@
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::events::{self, Event, RecordedSession};
+use crate::period::Period;
+use crate::timesheet::Timesheet;
+use crate::util;
+
+/// Working time rules, from the settings.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ComplianceRules {
+    /// Hours of work after which a break is due.
+    pub break_after: f64,
+    /// Minutes of break due after `break_after` hours.
+    pub break_minutes: u32,
+    /// Hours of work per day at most.
+    pub max_daily: f64,
+    /// Hours of rest between two working days at least.
+    pub min_rest: f64,
+}
+
+impl Default for ComplianceRules {
+    fn default() -> Self {
+        Self {
+            break_after: 6.0,
+            break_minutes: 30,
+            max_daily: 10.0,
+            min_rest: 11.0,
+        }
+    }
+}
+
+/// A day that broke one of the rules.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Violation {
+    MissingBreak {
+        date: NaiveDate,
+        worked: Duration,
+        taken: Duration,
+    },
+    TooLong {
+        date: NaiveDate,
+        worked: Duration,
+    },
+    /// Too little rest between the end of the previous working day and the start of this one.
+    ShortRest {
+        date: NaiveDate,
+        rest: Duration,
+    },
+}
+
+impl ComplianceRules {
+    fn break_after(&self) -> Duration {
+        hours(self.break_after)
+    }
+
+    fn break_due(&self) -> Duration {
+        Duration::minutes(self.break_minutes as i64)
+    }
+
+    fn max_daily(&self) -> Duration {
+        hours(self.max_daily)
+    }
+
+    fn min_rest(&self) -> Duration {
+        hours(self.min_rest)
+    }
+
+    /// The violations in `period`. Breaks and rest are only known for days with sessions, the
+    /// daily maximum is checked against all hours, including hand-edited ones.
+    pub fn check(
+        &self,
+        timesheet: &Timesheet,
+        events: &[Event],
+        period: &Period,
+    ) -> Vec<Violation> {
+        let sessions: Vec<_> = events::sessions(events)
+            .into_iter()
+            .filter(|session| !session.open)
+            .collect();
+        let mut previous_end = last_end_before(&sessions, period.from);
+        let mut violations = Vec::new();
+        for date in period.days() {
+            let worked = timesheet.get_hours(&date);
+            if worked > self.max_daily() {
+                violations.push(Violation::TooLong { date, worked });
+            }
+            let day: Vec<_> = sessions.iter().filter(|s| s.date() == date).collect();
+            let (start, end) = match (
+                day.iter().map(|s| s.start).min(),
+                day.iter().map(|s| s.end).max(),
+            ) {
+                (Some(start), Some(end)) => (start, end),
+                _ => continue,
+            };
+            let worked = day
+                .iter()
+                .fold(Duration::zero(), |acc, session| acc + session.duration());
+            let taken = end - start - worked;
+            if worked > self.break_after() && taken < self.break_due() {
+                violations.push(Violation::MissingBreak {
+                    date,
+                    worked,
+                    taken,
+                });
+            }
+            if let Some(rest) = previous_end.map(|previous_end| start - previous_end) {
+                if rest < self.min_rest() {
+                    violations.push(Violation::ShortRest { date, rest });
+                }
+            }
+            previous_end = Some(end);
+        }
+        violations
+    }
+
+    /// Warnings while the timer runs, with `done` worked today since `start`.
+    /// `previous_end` is when work ended before today.
+    pub fn warnings(
+        &self,
+        done: Duration,
+        start: DateTime<FixedOffset>,
+        previous_end: Option<DateTime<FixedOffset>>,
+        now: DateTime<FixedOffset>,
+    ) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if let Some(rest) = previous_end.map(|previous_end| start - previous_end) {
+            if rest < self.min_rest() {
+                warnings.push(format!(
+                    "only {} rest since the day before",
+                    util::format_duration(rest)
+                ));
+            }
+        }
+        let taken = (now - start - done).max(Duration::zero());
+        if done > self.break_after() && taken < self.break_due() {
+            warnings.push(format!(
+                "a break of {} is due",
+                util::format_duration(self.break_due() - taken)
+            ));
+        }
+        if done > self.max_daily() {
+            warnings.push(format!(
+                "over the maximum of {} a day",
+                util::format_duration(self.max_daily())
+            ));
+        }
+        warnings
+    }
+
+    /// Like `Mon 19.10.2026 worked 10:30:00, more than the maximum of 10:00:00`.
+    pub fn describe(&self, violation: &Violation) -> String {
+        let (date, description) = match *violation {
+            Violation::MissingBreak {
+                date,
+                worked,
+                taken,
+            } => (
+                date,
+                format!(
+                    "worked {} with {} of breaks, {} are due after {}",
+                    util::format_duration(worked),
+                    util::format_duration(taken),
+                    util::format_duration(self.break_due()),
+                    util::format_duration(self.break_after())
+                ),
+            ),
+            Violation::TooLong { date, worked } => (
+                date,
+                format!(
+                    "worked {}, more than the maximum of {}",
+                    util::format_duration(worked),
+                    util::format_duration(self.max_daily())
+                ),
+            ),
+            Violation::ShortRest { date, rest } => (
+                date,
+                format!(
+                    "started after {} of rest, {} are required",
+                    util::format_duration(rest),
+                    util::format_duration(self.min_rest())
+                ),
+            ),
+        };
+        format!(
+            "{} {} {}",
+            date.weekday(),
+            date.format("%d.%m.%Y"),
+            description
+        )
+    }
+
+    /// The violations of a month, one per line.
+    pub fn report(&self, timesheet: &Timesheet, events: &[Event], month: &Period) -> String {
+        let violations = self.check(timesheet, events, month);
+        let mut lines = vec![format!("Compliance for {}", month.name), String::new()];
+        if violations.is_empty() {
+            lines.push("No violations.".to_owned());
+            return lines.join("\n");
+        }
+        for violation in &violations {
+            lines.push(self.describe(violation));
+        }
+        lines.push(String::new());
+        lines.push(match violations.len() {
+            1 => "1 violation.".to_owned(),
+            n => format!("{} violations.", n),
+        });
+        lines.join("\n")
+    }
+}
+
+/// When the last session before `date` ended.
+pub fn last_end_before(
+    sessions: &[RecordedSession],
+    date: NaiveDate,
+) -> Option<DateTime<FixedOffset>> {
+    sessions
+        .iter()
+        .filter(|session| !session.open && session.date() < date)
+        .map(|session| session.end)
+        .max()
+}
+
+fn hours(hours: f64) -> Duration {
+    Duration::seconds((hours * 3600.0).round() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        let at = |day, hour, min| {
+            FixedOffset::east(7200)
+                .ymd(2026, 10, day)
+                .and_hms(hour, min, 0)
+        };
+        let session = |from, to| {
+            vec![
+                Event::SessionStarted {
+                    at: from,
+                    project: None,
+                    billable: true,
+                },
+                Event::Stopped { at: to },
+            ]
+        };
+        let events: Vec<_> = [
+            // Six and a half hours with a 15 minute break.
+            session(at(12, 8, 0), at(12, 12, 0)),
+            session(at(12, 12, 15), at(12, 14, 45)),
+            // Exactly six hours without a break until 22:00, then eleven in one go after only
+            // ten hours of rest.
+            session(at(13, 16, 0), at(13, 22, 0)),
+            session(at(14, 8, 0), at(14, 19, 0)),
+            // Exactly the maximum of ten hours.
+            session(at(16, 8, 0), at(16, 13, 0)),
+            session(at(16, 13, 30), at(16, 18, 30)),
+        ]
+        .concat();
+        let timesheet = Timesheet::from_events(&events);
+        let rules = ComplianceRules::default();
+
+        let violations = rules.check(&timesheet, &events, &Period::month(2026, 10));
+
+        assert_eq!(
+            violations,
+            vec![
+                Violation::MissingBreak {
+                    date: NaiveDate::from_ymd(2026, 10, 12),
+                    worked: Duration::minutes(6 * 60 + 30),
+                    taken: Duration::minutes(15),
+                },
+                Violation::TooLong {
+                    date: NaiveDate::from_ymd(2026, 10, 14),
+                    worked: Duration::hours(11),
+                },
+                Violation::MissingBreak {
+                    date: NaiveDate::from_ymd(2026, 10, 14),
+                    worked: Duration::hours(11),
+                    taken: Duration::zero(),
+                },
+                Violation::ShortRest {
+                    date: NaiveDate::from_ymd(2026, 10, 14),
+                    rest: Duration::hours(10),
+                },
+            ]
+        );
+        assert_eq!(
+            rules.describe(&violations[0]),
+            "Mon 12.10.2026 worked 06:30:00 with 00:15:00 of breaks, 00:30:00 are due after 06:00:00"
+        );
+        assert!(rules
+            .check(&timesheet, &events, &Period::month(2026, 9))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_warnings() {
+        let start = FixedOffset::east(7200).ymd(2026, 10, 19).and_hms(8, 0, 0);
+        let rules = ComplianceRules::default();
+
+        assert!(rules
+            .warnings(Duration::hours(5), start, None, start + Duration::hours(5))
+            .is_empty());
+        // Exactly six or ten hours don't break the rules yet, as in `check`.
+        assert!(rules
+            .warnings(Duration::hours(6), start, None, start + Duration::hours(6))
+            .is_empty());
+        assert!(rules
+            .warnings(
+                Duration::hours(10),
+                start,
+                None,
+                start + Duration::minutes(10 * 60 + 30)
+            )
+            .is_empty());
+        assert_eq!(
+            rules.warnings(
+                Duration::minutes(6 * 60 + 1),
+                start,
+                Some(start - Duration::hours(9)),
+                start + Duration::minutes(6 * 60 + 11)
+            ),
+            vec![
+                "only 09:00:00 rest since the day before",
+                "a break of 00:20:00 is due"
+            ]
+        );
+        assert_eq!(
+            rules.warnings(
+                Duration::minutes(10 * 60 + 1),
+                start,
+                None,
+                start + Duration::minutes(10 * 60 + 31)
+            ),
+            vec!["over the maximum of 10:00:00 a day"]
+        );
+    }
+}
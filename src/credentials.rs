@@ -0,0 +1,280 @@
+use std::fmt;
+use std::fs;
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use openssl::base64;
+use openssl::hash::MessageDigest;
+use openssl::pkcs5;
+use openssl::rand;
+use openssl::symm::{self, Cipher};
+use serde::{Deserialize, Serialize};
+
+/// Environment variable checked when the settings don't name a source for the API key.
+pub const DEFAULT_ENV_VAR: &str = "HOURS_API_KEY";
+
+const FILE_HEADER: &str = "hours-encrypted-key-v1";
+const PBKDF2_ITERATIONS: usize = 200_000;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+
+/// A string that is never shown by `Debug` or `Display`, so it can't end up in a log or panic.
+#[derive(Clone, Default, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(s: String) -> Self {
+        Self(s)
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Secret(<redacted>)")
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ApiKeySource {
+    Plain(Secret),
+    Env(String),
+    Command(String),
+    EncryptedFile(PathBuf),
+}
+
+#[derive(Debug)]
+pub enum CredentialError {
+    NoSource,
+    MultipleSources,
+    EnvNotSet(String),
+    CommandFailed(String, String),
+    Empty,
+    FileUnreadable(PathBuf, String),
+    FileMalformed(PathBuf),
+    WrongPassphrase(PathBuf),
+}
+
+impl fmt::Display for CredentialError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoSource => write!(
+                f,
+                "no API key configured, set one of api_key, api_key_env, api_key_command or \
+                 api_key_file in the settings, or the {} environment variable",
+                DEFAULT_ENV_VAR
+            ),
+            Self::MultipleSources => write!(
+                f,
+                "only one of api_key, api_key_env, api_key_command and api_key_file may be set"
+            ),
+            Self::EnvNotSet(var) => write!(f, "environment variable {} is not set", var),
+            Self::CommandFailed(command, reason) => {
+                write!(f, "command \"{}\" failed: {}", command, reason)
+            }
+            Self::Empty => write!(f, "the API key is empty"),
+            Self::FileUnreadable(path, reason) => {
+                write!(f, "could not read \"{}\": {}", path.display(), reason)
+            }
+            Self::FileMalformed(path) => {
+                write!(f, "\"{}\" is not an encrypted key file", path.display())
+            }
+            Self::WrongPassphrase(path) => {
+                write!(f, "wrong passphrase for \"{}\"", path.display())
+            }
+        }
+    }
+}
+
+impl ApiKeySource {
+    /// Fetches the key. `passphrase` is only called for encrypted files.
+    pub fn resolve(&self, passphrase: impl FnOnce() -> String) -> Result<Secret, CredentialError> {
+        let key = match self {
+            Self::Plain(secret) => secret.clone(),
+            Self::Env(var) => std::env::var(var)
+                .map(Secret)
+                .map_err(|_| CredentialError::EnvNotSet(var.clone()))?,
+            Self::Command(command) => run_command(command)?,
+            Self::EncryptedFile(path) => {
+                let content = fs::read_to_string(path)
+                    .map_err(|e| CredentialError::FileUnreadable(path.clone(), e.to_string()))?;
+                decrypt(&content, &passphrase()).map_err(|e| match e {
+                    DecryptError::Malformed => CredentialError::FileMalformed(path.clone()),
+                    DecryptError::WrongPassphrase => CredentialError::WrongPassphrase(path.clone()),
+                })?
+            }
+        };
+        if key.0.trim().is_empty() {
+            return Err(CredentialError::Empty);
+        }
+        Ok(Secret(key.0.trim().to_owned()))
+    }
+}
+
+fn run_command(command: &str) -> Result<Secret, CredentialError> {
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .output()
+        .map_err(|e| CredentialError::CommandFailed(command.to_owned(), e.to_string()))?;
+    if !output.status.success() {
+        // The output is deliberately not included, it might contain the secret.
+        return Err(CredentialError::CommandFailed(
+            command.to_owned(),
+            output.status.to_string(),
+        ));
+    }
+    String::from_utf8(output.stdout).map(Secret).map_err(|_| {
+        CredentialError::CommandFailed(command.to_owned(), "output is not UTF-8".to_owned())
+    })
+}
+
+/// Writes `secret` to `path` encrypted with a key derived from `passphrase`, readable only by the
+/// current user.
+pub fn write_encrypted_file(path: &Path, secret: &Secret, passphrase: &str) {
+    let content = encrypt(secret, passphrase);
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)
+        .unwrap();
+    file.write_all(content.as_bytes()).unwrap();
+}
+
+fn encrypt(secret: &Secret, passphrase: &str) -> String {
+    let mut salt = [0; SALT_LEN];
+    let mut nonce = [0; NONCE_LEN];
+    rand::rand_bytes(&mut salt).unwrap();
+    rand::rand_bytes(&mut nonce).unwrap();
+    let key = derive_key(passphrase, &salt);
+    let mut tag = [0; TAG_LEN];
+    let mut ciphertext = symm::encrypt_aead(
+        Cipher::aes_256_gcm(),
+        &key,
+        Some(&nonce),
+        FILE_HEADER.as_bytes(),
+        secret.0.as_bytes(),
+        &mut tag,
+    )
+    .unwrap();
+    ciphertext.extend_from_slice(&tag);
+    format!(
+        "{}\n{}\n{}\n{}\n",
+        FILE_HEADER,
+        base64::encode_block(&salt),
+        base64::encode_block(&nonce),
+        base64::encode_block(&ciphertext)
+    )
+}
+
+#[derive(Debug, PartialEq)]
+enum DecryptError {
+    Malformed,
+    WrongPassphrase,
+}
+
+fn decrypt(content: &str, passphrase: &str) -> Result<Secret, DecryptError> {
+    let mut lines = content.lines();
+    if lines.next() != Some(FILE_HEADER) {
+        return Err(DecryptError::Malformed);
+    }
+    let mut next_block = || {
+        lines
+            .next()
+            .and_then(|line| base64::decode_block(line).ok())
+            .ok_or(DecryptError::Malformed)
+    };
+    let salt = next_block()?;
+    let nonce = next_block()?;
+    let ciphertext = next_block()?;
+    if ciphertext.len() < TAG_LEN {
+        return Err(DecryptError::Malformed);
+    }
+    let (ciphertext, tag) = ciphertext.split_at(ciphertext.len() - TAG_LEN);
+    let key = derive_key(passphrase, &salt);
+    let plaintext = symm::decrypt_aead(
+        Cipher::aes_256_gcm(),
+        &key,
+        Some(&nonce),
+        FILE_HEADER.as_bytes(),
+        ciphertext,
+        tag,
+    )
+    .map_err(|_| DecryptError::WrongPassphrase)?;
+    String::from_utf8(plaintext)
+        .map(Secret)
+        .map_err(|_| DecryptError::Malformed)
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> [u8; 32] {
+    let mut key = [0; 32];
+    pkcs5::pbkdf2_hmac(
+        passphrase.as_bytes(),
+        salt,
+        PBKDF2_ITERATIONS,
+        MessageDigest::sha256(),
+        &mut key,
+    )
+    .unwrap();
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_decrypt() {
+        let secret = Secret("ghp_secret".to_owned());
+        let content = encrypt(&secret, "correct horse");
+
+        assert!(!content.contains("ghp_secret"));
+        assert_eq!(
+            decrypt(&content, "correct horse").unwrap().expose(),
+            "ghp_secret"
+        );
+        assert_eq!(
+            decrypt(&content, "wrong").unwrap_err(),
+            DecryptError::WrongPassphrase
+        );
+        assert_eq!(
+            decrypt("not a key file", "correct horse").unwrap_err(),
+            DecryptError::Malformed
+        );
+    }
+
+    #[test]
+    fn test_secret_is_redacted() {
+        let secret = Secret("ghp_secret".to_owned());
+
+        assert!(!format!("{:?}", secret).contains("ghp_secret"));
+        assert!(!format!("{:?}", ApiKeySource::Plain(secret)).contains("ghp_secret"));
+    }
+
+    #[test]
+    fn test_command_source() {
+        let source = ApiKeySource::Command("echo ' ghp_secret '".to_owned());
+
+        assert_eq!(
+            source.resolve(|| unreachable!()).unwrap().expose(),
+            "ghp_secret"
+        );
+    }
+
+    #[test]
+    fn test_failed_command_does_not_leak_output() {
+        let source = ApiKeySource::Command("printf ghp_%s secret; exit 1".to_owned());
+
+        let error = source.resolve(|| unreachable!()).unwrap_err().to_string();
+        assert!(!error.contains("ghp_secret"));
+    }
+}
@@ -0,0 +1,457 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::io::AsRawFd;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::process;
+use std::sync::{Arc, Mutex};
+
+use chrono::{Duration, Local};
+
+use crate::events::{self, EventLog};
+use crate::leave::LeaveMode;
+use crate::lock::{Instance, InstanceLock};
+use crate::period::Period;
+use crate::remaining_work::{WeeklyWork, Workday};
+use crate::session::{self, Cancel, Session, SharedSession, SuspendPolicy};
+use crate::settings::Settings;
+use crate::timesheet::Timesheet;
+use crate::{clock, compliance, paths, terminal, util};
+
+/// A command sent to the daemon, one per connection, as a single line.
+#[derive(Debug, PartialEq)]
+pub enum Request {
+    Start {
+        project: Option<String>,
+        billable: bool,
+    },
+    Stop,
+    Pause,
+    Resume,
+    Status,
+    Switch {
+        project: String,
+        billable: bool,
+    },
+    Shutdown,
+}
+
+impl Request {
+    fn parse(line: &str) -> Option<Self> {
+        let mut pieces = line.trim().splitn(2, ' ');
+        let command = pieces.next()?;
+        let argument = pieces.next().map(|s| s.trim());
+        // Sessions are billable unless the project is preceded by `--non-billable`.
+        let (argument, billable) = match argument {
+            Some("--non-billable") => (None, false),
+            Some(argument) => match argument.strip_prefix("--non-billable ") {
+                Some(project) => (Some(project.trim().to_owned()), false),
+                None => (Some(argument.to_owned()), true),
+            },
+            None => (None, true),
+        };
+        match (command, argument) {
+            ("start", project) => Some(Self::Start { project, billable }),
+            ("switch", Some(project)) => Some(Self::Switch { project, billable }),
+            (_, _) if !billable => None,
+            ("stop", None) => Some(Self::Stop),
+            ("pause", None) => Some(Self::Pause),
+            ("resume", None) => Some(Self::Resume),
+            ("status", None) => Some(Self::Status),
+            ("shutdown", None) => Some(Self::Shutdown),
+            _ => None,
+        }
+    }
+
+    fn to_line(&self) -> String {
+        let task = |project: Option<&String>, billable: bool| {
+            let mut line = String::new();
+            if !billable {
+                line.push_str(" --non-billable");
+            }
+            if let Some(project) = project {
+                line.push(' ');
+                line.push_str(project);
+            }
+            line
+        };
+        match self {
+            Self::Start { project, billable } => {
+                format!("start{}", task(project.as_ref(), *billable))
+            }
+            Self::Stop => "stop".to_owned(),
+            Self::Pause => "pause".to_owned(),
+            Self::Resume => "resume".to_owned(),
+            Self::Status => "status".to_owned(),
+            Self::Switch { project, billable } => {
+                format!("switch{}", task(Some(project), *billable))
+            }
+            Self::Shutdown => "shutdown".to_owned(),
+        }
+    }
+}
+
+/// The daemon's answer, sent back as `ok <message>` or `error <message>`.
+pub type Response = Result<String, String>;
+
+pub enum ClientError {
+    NotRunning,
+    Io(io::Error),
+}
+
+/// Sends `request` to the running daemon and waits for its response.
+pub fn send(request: &Request) -> Result<Response, ClientError> {
+    let mut stream = UnixStream::connect(paths::socket_file()).map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound | io::ErrorKind::ConnectionRefused => ClientError::NotRunning,
+        _ => ClientError::Io(e),
+    })?;
+    writeln!(stream, "{}", request.to_line()).map_err(ClientError::Io)?;
+    let mut line = String::new();
+    BufReader::new(stream)
+        .read_line(&mut line)
+        .map_err(ClientError::Io)?;
+    let line = line.trim_end();
+    match line.split_once(' ') {
+        Some(("ok", message)) => Ok(Ok(message.to_owned())),
+        Some(("error", message)) => Ok(Err(message.to_owned())),
+        _ => Err(ClientError::Io(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unexpected response \"{}\"", line),
+        ))),
+    }
+}
+
+/// Runs the daemon until it receives `shutdown`, detached from the terminal unless `foreground`
+/// is set. Sessions it tracks are booked when they're stopped, switched or when the daemon shuts
+/// down.
+pub fn run(mut instance_lock: InstanceLock, force: bool, foreground: bool) {
+    let log = EventLog::open();
+    log.prepare(force);
+    let recovered_session = session::recover_interrupted_session(&log, terminal::is_interactive());
+    let socket_path = paths::socket_file();
+    // We hold the lock, so a socket file left behind can only be from a daemon that died.
+    let _ = fs::remove_file(&socket_path);
+    let listener = UnixListener::bind(&socket_path).unwrap_or_else(|e| {
+        util::exit_with_error(format!(
+            "Could not listen on \"{}\": {}",
+            socket_path.display(),
+            e
+        ))
+    });
+    if !foreground {
+        detach();
+    }
+    let mut instance = Instance::new(Local::now(), Timesheet::load().get_hours(&clock::today()));
+    instance.daemon = true;
+    instance_lock.record(&instance);
+    println!(
+        "Listening on \"{}\" (PID {}).",
+        socket_path.display(),
+        instance.pid
+    );
+
+    // Nobody can be asked about suspends here, so they aren't counted unless configured to.
+    let suspend_policy = match Settings::try_load().map(|settings| settings.suspended_time) {
+        Some(SuspendPolicy::Count) => SuspendPolicy::Count,
+        _ => SuspendPolicy::Exclude,
+    };
+    let session: SharedSession = Arc::new(Mutex::new(recovered_session));
+    let (handle, heartbeat_cancel_sender) =
+        session::write_heartbeats_in_background(session.clone(), suspend_policy);
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("Could not accept connection: {}", e);
+                continue;
+            }
+        };
+        match handle_connection(stream, &session, &log) {
+            Ok(true) => break,
+            Ok(false) => {}
+            Err(e) => eprintln!("Could not handle request: {}", e),
+        }
+    }
+    drop(listener);
+    let _ = fs::remove_file(&socket_path);
+    heartbeat_cancel_sender.send(Cancel).unwrap();
+    handle.join().unwrap();
+}
+
+/// Continues in a child process that has a session of its own, so it survives the terminal or SSH
+/// session closing, and writes its output to the daemon's log file. The parent process exits.
+/// The lock and the socket are inherited by the child.
+fn detach() {
+    let log_path = paths::daemon_log_file();
+    let log_file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&log_path)
+        .unwrap_or_else(|e| {
+            util::exit_with_error(format!("Could not open \"{}\": {}", log_path.display(), e))
+        });
+    let null = File::open("/dev/null").unwrap();
+    io::stdout().flush().unwrap();
+    match unsafe { libc::fork() } {
+        -1 => util::exit_with_error(format!(
+            "Could not start the daemon: {}",
+            io::Error::last_os_error()
+        )),
+        0 => {}
+        pid => {
+            println!(
+                "Started the daemon in the background (PID {}), logging to \"{}\".",
+                pid,
+                log_path.display()
+            );
+            process::exit(0);
+        }
+    }
+    unsafe {
+        libc::setsid();
+        libc::signal(libc::SIGHUP, libc::SIG_IGN);
+        libc::dup2(null.as_raw_fd(), libc::STDIN_FILENO);
+        libc::dup2(log_file.as_raw_fd(), libc::STDOUT_FILENO);
+        libc::dup2(log_file.as_raw_fd(), libc::STDERR_FILENO);
+    }
+}
+
+/// Returns `true` if the daemon should shut down.
+fn handle_connection(
+    stream: UnixStream,
+    session: &SharedSession,
+    log: &EventLog,
+) -> io::Result<bool> {
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line)?;
+    let request = Request::parse(&line);
+    let shutdown = request == Some(Request::Shutdown);
+    let response = match request {
+        Some(request) => handle_request(request, &mut session.lock().unwrap(), log),
+        None => Err(format!("unknown command \"{}\"", line.trim())),
+    };
+    let mut stream = stream;
+    match response {
+        Ok(message) => writeln!(stream, "ok {}", message)?,
+        Err(message) => writeln!(stream, "error {}", message)?,
+    }
+    Ok(shutdown)
+}
+
+fn handle_request(request: Request, session: &mut Option<Session>, log: &EventLog) -> Response {
+    match request {
+        Request::Start { project, billable } => match session {
+            Some(running) => Err(format!("already running for {}", describe(running))),
+            None => {
+                *session = Some(Session::start(log.clone(), project, billable));
+                Ok(format!("started{}", on_project(session.as_ref().unwrap())))
+            }
+        },
+        Request::Stop | Request::Shutdown => match session.take() {
+            Some(stopped) => {
+                stopped.book();
+                Ok(format!("stopped after {}", describe(&stopped)))
+            }
+            None if request == Request::Shutdown => Ok("shut down".to_owned()),
+            None => Err("not running".to_owned()),
+        },
+        Request::Pause => match session {
+            Some(running) => match running.pause() {
+                true => Ok(format!("paused after {}", describe(running))),
+                false => Err("already paused".to_owned()),
+            },
+            None => Err("not running".to_owned()),
+        },
+        Request::Resume => match session {
+            Some(paused) => match paused.resume() {
+                true => Ok(format!("resumed{}", on_project(paused))),
+                false => Err("not paused".to_owned()),
+            },
+            None => Err("not running".to_owned()),
+        },
+        Request::Status => {
+            let timesheet = Timesheet::load();
+            let elapsed = session.as_ref().map_or(Duration::zero(), Session::elapsed);
+            let today = session
+                .as_ref()
+                .map_or(clock::today(), |running| running.date);
+            let mut status = match session {
+                Some(running) => format!(
+                    "{} for {}, {} today",
+                    if running.is_paused() {
+                        "paused"
+                    } else {
+                        "running"
+                    },
+                    describe(running),
+                    util::format_duration(timesheet.get_hours(&today) + elapsed)
+                ),
+                None => format!(
+                    "not running, {} today",
+                    util::format_duration(timesheet.get_hours(&today))
+                ),
+            };
+            let settings = Settings::try_load();
+            let lunch_break = settings
+                .as_ref()
+                .map_or(Duration::zero(), Settings::lunch_break);
+            // Like the timer, about the day the running session is booked on.
+            if let Some(workday) = Workday::on(today, &timesheet, &log.read(), lunch_break) {
+                let done = timesheet.get_hours(&today) + elapsed;
+                status.push_str(", ");
+                status.push_str(&workday.countdown(done, clock::now()));
+            }
+            let rules = settings.as_ref().and_then(|settings| settings.compliance);
+            let events = log.read();
+            if let (Some(rules), Some(start)) = (rules, events::day_start(&events, today)) {
+                let done = timesheet.get_hours(&today) + elapsed;
+                let previous_end = compliance::last_end_before(&events::sessions(&events), today);
+                for warning in rules.warnings(done, start, previous_end, clock::now()) {
+                    status.push_str(", ");
+                    status.push_str(&warning);
+                }
+            }
+            if let Some(target) = settings.as_ref().and_then(Settings::weekly_target) {
+                let done = Period::week(today).hours(&timesheet) + elapsed;
+                let mode = settings
+                    .as_ref()
+                    .map_or(LeaveMode::default(), |s| s.leave.mode);
+                let work = WeeklyWork::new(today, done, target).with_leave(&timesheet, mode);
+                status.push_str(", ");
+                status.push_str(&work.progress());
+            }
+            if let Some(overtime) = settings.and_then(|settings| settings.overtime) {
+                let mut balance = overtime.balance(&timesheet, today);
+                balance.balance = balance.balance + elapsed;
+                status.push_str(", ");
+                status.push_str(&balance.short());
+            }
+            Ok(status)
+        }
+        Request::Switch { project, billable } => {
+            let message = match session.take() {
+                Some(stopped) => {
+                    stopped.book();
+                    format!("stopped after {}, ", describe(&stopped))
+                }
+                None => String::new(),
+            };
+            *session = Some(Session::start(log.clone(), Some(project), billable));
+            Ok(format!(
+                "{}started{}",
+                message,
+                on_project(session.as_ref().unwrap())
+            ))
+        }
+    }
+}
+
+fn describe(session: &Session) -> String {
+    let excluded = if session.excluded() > Duration::zero() {
+        format!(
+            " (not counting {} suspended)",
+            util::format_duration(session.excluded())
+        )
+    } else {
+        String::new()
+    };
+    format!(
+        "{}{}{}",
+        util::format_duration(session.elapsed()),
+        on_project(session),
+        excluded
+    )
+}
+
+fn on_project(session: &Session) -> String {
+    let non_billable = if session.billable {
+        ""
+    } else {
+        " (non-billable)"
+    };
+    match &session.project {
+        Some(project) => format!(" on {}{}", project, non_billable),
+        None => non_billable.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_request() {
+        let start = |project: Option<&str>, billable| Request::Start {
+            project: project.map(str::to_owned),
+            billable,
+        };
+        let cases = [
+            ("start", Some(start(None, true))),
+            (
+                "start client work\n",
+                Some(start(Some("client work"), true)),
+            ),
+            ("start --non-billable", Some(start(None, false))),
+            (
+                "start --non-billable internal",
+                Some(start(Some("internal"), false)),
+            ),
+            ("stop", Some(Request::Stop)),
+            ("status\n", Some(Request::Status)),
+            (
+                "switch acme",
+                Some(Request::Switch {
+                    project: "acme".to_owned(),
+                    billable: true,
+                }),
+            ),
+            ("switch", None),
+            ("switch --non-billable", None),
+            ("stop now", None),
+            ("stop --non-billable", None),
+            ("restart", None),
+        ];
+
+        for (line, expected) in cases.iter() {
+            let request = Request::parse(line);
+            assert_eq!(&request, expected);
+            if let Some(request) = request {
+                assert_eq!(Request::parse(&request.to_line()), Some(request));
+            }
+        }
+    }
+
+    #[test]
+    fn test_session_commands() {
+        let dir = TempDir::new("daemon");
+        let log = EventLog::at(dir.join("hours.events.jsonl"));
+        let mut session = None;
+
+        assert!(handle_request(Request::Pause, &mut session, &log).is_err());
+        assert!(handle_request(
+            Request::Start {
+                project: Some("acme".to_owned()),
+                billable: true
+            },
+            &mut session,
+            &log
+        )
+        .is_ok());
+        assert!(handle_request(
+            Request::Start {
+                project: None,
+                billable: false
+            },
+            &mut session,
+            &log
+        )
+        .is_err());
+        assert!(handle_request(Request::Pause, &mut session, &log).is_ok());
+        assert!(handle_request(Request::Pause, &mut session, &log).is_err());
+        assert!(handle_request(Request::Resume, &mut session, &log).is_ok());
+        assert!(handle_request(Request::Resume, &mut session, &log).is_err());
+        assert_eq!(session.as_ref().unwrap().project.as_deref(), Some("acme"));
+        assert_eq!(log.read().len(), 3);
+    }
+}
@@ -0,0 +1,607 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::leave::LeaveKind;
+use crate::report::{Report, ReportFormat};
+use crate::timesheet::{ClosedMonthError, ParseError, Timesheet};
+use crate::{clock, paths, util};
+
+/// Something that happened to the timesheet. The timesheet is derived from the sequence of all
+/// events, see `Timesheet::from_events`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    SessionStarted {
+        at: DateTime<FixedOffset>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        project: Option<String>,
+        #[serde(default = "billable", skip_serializing_if = "is_billable")]
+        billable: bool,
+    },
+    /// Written regularly while a session runs, so an interrupted session has a known end.
+    Heartbeat {
+        at: DateTime<FixedOffset>,
+    },
+    Paused {
+        at: DateTime<FixedOffset>,
+    },
+    Resumed {
+        at: DateTime<FixedOffset>,
+    },
+    Stopped {
+        at: DateTime<FixedOffset>,
+    },
+    /// A suspend of the running session, recorded as a pause from `from` to `to`, is counted after
+    /// all.
+    SuspendCounted {
+        from: DateTime<FixedOffset>,
+        to: DateTime<FixedOffset>,
+    },
+    /// Hours added to (or, if negative, removed from) a day outside of a session.
+    Adjusted {
+        date: NaiveDate,
+        seconds: i64,
+        reason: String,
+    },
+    /// Pomodoros completed on the day, or if negative, removed from it.
+    Pomodoros {
+        date: NaiveDate,
+        count: i32,
+    },
+    /// The day was taken off.
+    Leave {
+        date: NaiveDate,
+        kind: LeaveKind,
+    },
+    /// The day is a normal day again.
+    LeaveRemoved {
+        date: NaiveDate,
+    },
+    /// The month was closed with `hours close`, after which its hours only change with `--force`.
+    MonthClosed {
+        at: DateTime<FixedOffset>,
+        year: i32,
+        month: u32,
+        checksum: String,
+    },
+}
+
+fn billable() -> bool {
+    true
+}
+
+fn is_billable(billable: &bool) -> bool {
+    *billable
+}
+
+/// A session as recorded in the event log.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedSession {
+    pub project: Option<String>,
+    pub billable: bool,
+    pub start: DateTime<FixedOffset>,
+    /// When the session stopped, or its last event if it's still open.
+    pub end: DateTime<FixedOffset>,
+    pub pauses: Vec<(DateTime<FixedOffset>, DateTime<FixedOffset>)>,
+    /// Whether the session has no `Stopped` event, because it's running or was interrupted.
+    pub open: bool,
+}
+
+impl RecordedSession {
+    /// Time tracked between the start and `end`, not counting pauses.
+    pub fn duration_until(&self, end: DateTime<FixedOffset>) -> Duration {
+        let paused = self
+            .pauses
+            .iter()
+            .map(|&(from, to)| clamp(to, self.start, end) - clamp(from, self.start, end))
+            .fold(Duration::zero(), |acc, pause| acc + pause);
+        let total = clamp(end, self.start, end) - self.start - paused;
+        if total < Duration::zero() {
+            Duration::zero()
+        } else {
+            total
+        }
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration_until(self.end)
+    }
+
+    /// The day the session is booked on, in the home timezone.
+    pub fn date(&self) -> NaiveDate {
+        clock::date(self.start)
+    }
+}
+
+fn clamp(
+    time: DateTime<FixedOffset>,
+    from: DateTime<FixedOffset>,
+    to: DateTime<FixedOffset>,
+) -> DateTime<FixedOffset> {
+    if time < from {
+        from
+    } else if time > to {
+        to
+    } else {
+        time
+    }
+}
+
+/// Groups the session events into sessions, in the order they started.
+pub fn sessions(events: &[Event]) -> Vec<RecordedSession> {
+    let mut sessions = Vec::new();
+    let mut open: Option<(RecordedSession, Option<DateTime<FixedOffset>>)> = None;
+    for event in events {
+        match event {
+            Event::SessionStarted {
+                at,
+                project,
+                billable,
+            } => {
+                // A session without a stop is closed by the next start, at its last event.
+                if let Some((session, _)) = open.take() {
+                    sessions.push(RecordedSession {
+                        open: false,
+                        ..session
+                    });
+                }
+                let session = RecordedSession {
+                    project: project.clone(),
+                    billable: *billable,
+                    start: *at,
+                    end: *at,
+                    pauses: Vec::new(),
+                    open: true,
+                };
+                open = Some((session, None));
+            }
+            Event::Heartbeat { at } => {
+                if let Some((session, _)) = &mut open {
+                    session.end = session.end.max(*at);
+                }
+            }
+            Event::Paused { at } => {
+                if let Some((session, paused_since)) = &mut open {
+                    session.end = session.end.max(*at);
+                    paused_since.get_or_insert(*at);
+                }
+            }
+            Event::Resumed { at } => {
+                if let Some((session, paused_since)) = &mut open {
+                    session.end = session.end.max(*at);
+                    if let Some(from) = paused_since.take() {
+                        session.pauses.push((from, *at));
+                    }
+                }
+            }
+            Event::SuspendCounted { from, to } => {
+                if let Some((session, _)) = &mut open {
+                    session.pauses.retain(|&pause| pause != (*from, *to));
+                }
+            }
+            Event::Stopped { at } => {
+                if let Some((mut session, paused_since)) = open.take() {
+                    session.end = *at;
+                    if let Some(from) = paused_since {
+                        session.pauses.push((from, *at));
+                    }
+                    sessions.push(RecordedSession {
+                        open: false,
+                        ..session
+                    });
+                }
+            }
+            Event::Adjusted { .. }
+            | Event::Pomodoros { .. }
+            | Event::Leave { .. }
+            | Event::LeaveRemoved { .. }
+            | Event::MonthClosed { .. } => {}
+        }
+    }
+    if let Some((mut session, paused_since)) = open {
+        if let Some(from) = paused_since {
+            session.pauses.push((from, session.end));
+        }
+        sessions.push(session);
+    }
+    sessions
+}
+
+/// When the first session booked on `date` started.
+pub fn day_start(events: &[Event], date: NaiveDate) -> Option<DateTime<FixedOffset>> {
+    sessions(events)
+        .iter()
+        .filter(|session| session.date() == date)
+        .map(|session| session.start)
+        .min()
+}
+
+/// Why a report couldn't be recorded in the event log.
+#[derive(Debug)]
+pub enum ReconcileError {
+    Unreadable(ParseError),
+    Closed(ClosedMonthError),
+}
+
+/// The append-only file all events are written to, one JSON object per line.
+#[derive(Debug, Clone)]
+pub struct EventLog {
+    path: PathBuf,
+}
+
+impl EventLog {
+    pub fn open() -> Self {
+        Self {
+            path: paths::event_log_file(),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn at(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    pub fn exists(&self) -> bool {
+        self.path.exists()
+    }
+
+    pub fn append(&self, event: &Event) {
+        self.append_all(std::slice::from_ref(event));
+    }
+
+    pub fn append_all(&self, events: &[Event]) {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).unwrap();
+        }
+        let mut lines = String::new();
+        for event in events {
+            lines.push_str(&serde_json::to_string(event).unwrap());
+            lines.push('\n');
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .unwrap();
+        file.write_all(lines.as_bytes()).unwrap();
+        file.sync_data().unwrap();
+    }
+
+    /// Reads all events. Lines that can't be parsed, like a line cut off by a crash, are skipped
+    /// with a warning.
+    pub fn read(&self) -> Vec<Event> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Vec::new(),
+            Err(e) => panic!("Could not read \"{}\": {}", self.path.display(), e),
+        };
+        content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .filter_map(|(i, line)| match serde_json::from_str(line) {
+                Ok(event) => Some(event),
+                Err(e) => {
+                    eprintln!(
+                        "Skipping line {} of \"{}\": {}",
+                        i + 1,
+                        self.path.display(),
+                        e
+                    );
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// The session that was started but not stopped, if any.
+    pub fn open_session(&self) -> Option<RecordedSession> {
+        sessions(&self.read()).pop().filter(|session| session.open)
+    }
+
+    /// Appends the changes of hours, pomodoros and leave that turn the current timesheet into
+    /// `target`, and the closings of months in `target` that aren't recorded yet. Returns whether
+    /// there were any changes.
+    ///
+    /// Nothing is appended if the hours of a closed month would change, unless `force` is set.
+    pub fn reconcile(
+        &self,
+        target: &Timesheet,
+        reason: &str,
+        force: bool,
+    ) -> Result<bool, ClosedMonthError> {
+        let current = Timesheet::from_events(&self.read());
+        if !force {
+            current.check_closed(target)?;
+        }
+        let mut events = Vec::new();
+        for date in current.dates_with(target) {
+            // The report only has whole seconds.
+            let difference = (target.get_hours(&date) - current.get_hours(&date)).num_seconds();
+            if difference != 0 {
+                events.push(Event::Adjusted {
+                    date,
+                    seconds: difference,
+                    reason: reason.to_owned(),
+                });
+            }
+        }
+        for date in current.dates_with(target) {
+            let difference = target.get_pomodoros(date) as i32 - current.get_pomodoros(date) as i32;
+            if difference != 0 {
+                events.push(Event::Pomodoros {
+                    date,
+                    count: difference,
+                });
+            }
+        }
+        let mut days_off: Vec<_> = current
+            .leave()
+            .chain(target.leave())
+            .map(|(d, _)| d)
+            .collect();
+        days_off.sort();
+        days_off.dedup();
+        for date in days_off {
+            match target.get_leave(date) {
+                kind if kind == current.get_leave(date) => {}
+                Some(kind) => events.push(Event::Leave { date, kind }),
+                None => events.push(Event::LeaveRemoved { date }),
+            }
+        }
+        for closing in target.closings() {
+            if current.closing(closing.year, closing.month) != Some(closing) {
+                events.push(Event::MonthClosed {
+                    at: clock::now(),
+                    year: closing.year,
+                    month: closing.month,
+                    checksum: closing.checksum.clone(),
+                });
+            }
+        }
+        self.append_all(&events);
+        Ok(!events.is_empty())
+    }
+
+    /// Like `reconcile`, but only for the days whose hours in `report` differ from the current
+    /// ones as far as the report's format shows. Decimal hours, for example, are rounded.
+    pub fn reconcile_report(
+        &self,
+        report: &Report,
+        reason: &str,
+        force: bool,
+    ) -> Result<bool, ReconcileError> {
+        let edited = Timesheet::try_parse_report(report).map_err(ReconcileError::Unreadable)?;
+        let format = ReportFormat::of(report).unwrap();
+        let current = Timesheet::from_events(&self.read());
+        let mut target = edited.without_hours();
+        for date in current.dates_with(&edited) {
+            let (hours, edited_hours) = (current.get_hours(&date), edited.get_hours(&date));
+            if format.format_duration(hours) == format.format_duration(edited_hours) {
+                target.add_hours(&date, &hours);
+            } else {
+                target.add_hours(&date, &edited_hours);
+            }
+        }
+        self.reconcile(&target, reason, force)
+            .map_err(ReconcileError::Closed)
+    }
+
+    /// Writes the report file if it doesn't match the events anymore. It's left alone otherwise,
+    /// because its modification time decides which side wins when syncing with the gist.
+    pub fn save_projection(&self) {
+        let report =
+            Timesheet::from_events(&self.read()).generate_report(&ReportFormat::configured());
+        if Report::load().0.trim() != report.0.trim() {
+            report.save();
+        }
+    }
+
+    /// Gets the event log in sync with the report file. Has to be called with the lock held.
+    ///
+    /// Without an event log, the hours in the report file are imported. Otherwise, if the report
+    /// file was edited by hand, the edits are recorded as adjustments. Edits to closed months
+    /// are refused unless `force` is set.
+    pub fn prepare(&self, force: bool) {
+        if !self.exists() {
+            Report::commit_backup();
+            let report = Report::load();
+            let timesheet = parse_report_or_exit(&report);
+            self.append_all(&[]);
+            self.reconcile(&timesheet, "imported from the report file", true)
+                .unwrap();
+        } else {
+            let report = Report::load();
+            let edited = !report.0.trim().is_empty()
+                && match self.reconcile_report(&report, "edited by hand", force) {
+                    Ok(edited) => edited,
+                    Err(ReconcileError::Unreadable(e)) => exit_unreadable_report(e),
+                    Err(ReconcileError::Closed(e)) => util::exit_with_error(format!(
+                        "\"{}\" was edited, but {}. Undo the changes to it, or use --force to \
+                         record them.",
+                        paths::report_file().display(),
+                        e
+                    )),
+                };
+            if edited {
+                println!(
+                    "\"{}\" was edited, the changes were recorded.",
+                    paths::report_file().display()
+                );
+            }
+        }
+        self.save_projection();
+    }
+}
+
+fn parse_report_or_exit(report: &Report) -> Timesheet {
+    Timesheet::try_parse_report(report).unwrap_or_else(|e| exit_unreadable_report(e))
+}
+
+fn exit_unreadable_report(e: ParseError) -> ! {
+    util::exit_with_error(format!(
+        "Could not read \"{}\", {}. Please fix it and start again.",
+        paths::report_file().display(),
+        e
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use crate::util::TempDir;
+
+    use super::*;
+
+    fn at(hour: u32, min: u32) -> DateTime<FixedOffset> {
+        FixedOffset::east(3600)
+            .ymd(2021, 3, 1)
+            .and_hms(hour, min, 0)
+    }
+
+    #[test]
+    fn test_sessions() {
+        let events = vec![
+            Event::SessionStarted {
+                at: at(9, 0),
+                project: Some("acme".to_owned()),
+                billable: true,
+            },
+            Event::Heartbeat { at: at(10, 0) },
+            Event::Paused { at: at(12, 0) },
+            Event::Resumed { at: at(12, 30) },
+            Event::Stopped { at: at(17, 0) },
+            Event::SessionStarted {
+                at: at(20, 0),
+                project: None,
+                billable: true,
+            },
+            Event::Heartbeat { at: at(21, 0) },
+            Event::Paused { at: at(21, 30) },
+        ];
+
+        let sessions = sessions(&events);
+
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].project.as_deref(), Some("acme"));
+        assert!(!sessions[0].open);
+        assert_eq!(
+            sessions[0].duration(),
+            Duration::hours(7) + Duration::minutes(30)
+        );
+        assert!(sessions[1].open);
+        assert_eq!(sessions[1].end, at(21, 30));
+        assert_eq!(sessions[1].duration(), Duration::minutes(90));
+    }
+
+    #[test]
+    fn test_timesheet_from_events() {
+        let events = vec![
+            Event::Adjusted {
+                date: NaiveDate::from_ymd(2021, 2, 26),
+                seconds: 3600,
+                reason: "imported".to_owned(),
+            },
+            Event::SessionStarted {
+                at: at(9, 0),
+                project: None,
+                billable: true,
+            },
+            Event::Stopped { at: at(10, 30) },
+            Event::Adjusted {
+                date: NaiveDate::from_ymd(2021, 3, 1),
+                seconds: -600,
+                reason: "edited by hand".to_owned(),
+            },
+            // Still running, so not included.
+            Event::SessionStarted {
+                at: at(11, 0),
+                project: None,
+                billable: true,
+            },
+            Event::Heartbeat { at: at(12, 0) },
+        ];
+
+        let timesheet = Timesheet::from_events(&events);
+
+        assert_eq!(
+            timesheet.generate_report(&ReportFormat::default()).0,
+            "# hours report v2, dates: dotted, durations: clock, language: en
+26.02.2021 01:00:00
+Total for February 2021 01:00:00
+
+01.03.2021 01:20:00
+Total for March 2021 01:20:00
+"
+        );
+    }
+
+    #[test]
+    fn test_reconcile() {
+        let dir = TempDir::new("events");
+        let log = EventLog::at(dir.join("hours.events.jsonl"));
+        log.append(&Event::Adjusted {
+            date: NaiveDate::from_ymd(2021, 3, 1),
+            seconds: 3600,
+            reason: "imported".to_owned(),
+        });
+        let target = Timesheet::parse_report(&Report(
+            "01.03.2021 02:00:00\n02.03.2021 00:30:00 1 pomodoro".to_owned(),
+        ));
+
+        log.reconcile(&target, "synced", false).unwrap();
+
+        let events = log.read();
+        assert_eq!(events.len(), 4);
+        assert_eq!(
+            Timesheet::from_events(&events)
+                .generate_report(&ReportFormat::default())
+                .0,
+            target.generate_report(&ReportFormat::default()).0
+        );
+    }
+
+    #[test]
+    fn test_reconcile_closed_month() {
+        let dir = TempDir::new("closed");
+        let log = EventLog::at(dir.join("hours.events.jsonl"));
+        let mut closed = Timesheet::parse_report(&Report("01.03.2021 02:00:00".to_owned()));
+        closed.close(2021, 3);
+        log.reconcile(&closed, "imported", false).unwrap();
+        let changed = Timesheet::parse_report(&Report(
+            "01.03.2021 03:00:00\n01.04.2021 01:00:00".to_owned(),
+        ));
+
+        let refused = log.reconcile(&changed, "synced", false);
+
+        assert_eq!(refused.unwrap_err().month.name, "March 2021");
+        let timesheet = Timesheet::from_events(&log.read());
+        assert_eq!(
+            timesheet.get_hours(&NaiveDate::from_ymd(2021, 4, 1)),
+            Duration::zero()
+        );
+        assert!(timesheet.is_intact(&timesheet.closings()[0]));
+        assert!(log.reconcile(&changed, "synced", true).unwrap());
+        let timesheet = Timesheet::from_events(&log.read());
+        assert_eq!(
+            timesheet.get_hours(&NaiveDate::from_ymd(2021, 3, 1)),
+            Duration::hours(3)
+        );
+        assert!(!timesheet.is_intact(&timesheet.closings()[0]));
+    }
+
+    #[test]
+    fn test_event_format() {
+        let event = Event::Stopped { at: at(17, 0) };
+
+        let line = serde_json::to_string(&event).unwrap();
+
+        assert!(line.starts_with(r#"{"event":"stopped","at":"2021-03-01T17:00:00"#));
+        assert_eq!(serde_json::from_str::<Event>(&line).unwrap(), event);
+    }
+}
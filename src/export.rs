@@ -0,0 +1,224 @@
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+use serde::Serialize;
+
+use crate::events::RecordedSession;
+use crate::period::Period;
+use crate::timesheet::Timesheet;
+use crate::util;
+
+#[derive(Debug, PartialEq)]
+pub enum Format {
+    Csv { delimiter: char, decimal: bool },
+    Json,
+    ICalendar,
+}
+
+/// An entry of the JSON export.
+#[derive(Serialize)]
+struct Entry {
+    date: NaiveDate,
+    duration: String,
+    seconds: i64,
+}
+
+/// One row per day, with the hours either as `HH:MM:SS` or, if `decimal`, as decimal hours.
+pub fn csv(
+    timesheet: &Timesheet,
+    period: Option<&Period>,
+    delimiter: char,
+    decimal: bool,
+) -> String {
+    let mut lines = vec![format!("date{}hours", delimiter)];
+    for (date, duration) in entries(timesheet, period) {
+        let hours = if decimal {
+            format!("{:.2}", duration.num_seconds() as f64 / 3600.0)
+        } else {
+            util::format_duration(duration)
+        };
+        lines.push(format!("{}{}{}", date, delimiter, hours));
+    }
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+pub fn json(timesheet: &Timesheet, period: Option<&Period>) -> String {
+    let entries: Vec<_> = entries(timesheet, period)
+        .map(|(date, duration)| Entry {
+            date,
+            duration: util::format_duration(duration),
+            seconds: duration.num_seconds(),
+        })
+        .collect();
+    serde_json::to_string_pretty(&entries).unwrap() + "\n"
+}
+
+/// An iCalendar file with an event for every session that was stopped.
+pub fn icalendar(sessions: &[RecordedSession], period: Option<&Period>) -> String {
+    let now = Utc::now();
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_owned(),
+        "VERSION:2.0".to_owned(),
+        "PRODID:-//hours//hours//EN".to_owned(),
+    ];
+    for session in sessions.iter().filter(|session| !session.open) {
+        if !period.is_none_or(|period| period.contains(session.date())) {
+            continue;
+        }
+        let summary = session.project.as_deref().unwrap_or("Work");
+        lines.extend(vec![
+            "BEGIN:VEVENT".to_owned(),
+            format!("UID:{}@hours", session.start.timestamp()),
+            format!("DTSTAMP:{}", format_time(now)),
+            format!("DTSTART:{}", format_time(session.start)),
+            format!("DTEND:{}", format_time(session.end)),
+            format!("SUMMARY:{}", escape_text(summary)),
+            format!(
+                "DESCRIPTION:{} tracked",
+                util::format_duration(session.duration())
+            ),
+            "END:VEVENT".to_owned(),
+        ]);
+    }
+    lines.push("END:VCALENDAR".to_owned());
+    lines.push(String::new());
+    // RFC 5545 wants CRLF line endings.
+    lines
+        .iter()
+        .map(|line| fold_line(line))
+        .collect::<Vec<_>>()
+        .join("\r\n")
+}
+
+fn entries<'a>(
+    timesheet: &'a Timesheet,
+    period: Option<&'a Period>,
+) -> impl Iterator<Item = (NaiveDate, Duration)> + 'a {
+    timesheet
+        .entries()
+        .filter(move |(date, _)| period.is_none_or(|period| period.contains(*date)))
+}
+
+fn format_time<Tz: TimeZone>(time: DateTime<Tz>) -> String {
+    time.with_timezone(&Utc)
+        .format("%Y%m%dT%H%M%SZ")
+        .to_string()
+}
+
+/// Splits a content line into lines of at most 75 octets, continued with a space, as RFC 5545
+/// wants, without splitting a UTF-8 character.
+fn fold_line(line: &str) -> String {
+    let mut folded = String::new();
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            // The space counts towards the continued line's length.
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+    folded
+}
+
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::FixedOffset;
+
+    use crate::report::Report;
+
+    use super::*;
+
+    fn sample_timesheet() -> Timesheet {
+        Timesheet::parse_report(&Report(
+            "31.08.2026 08:00:00\n01.09.2026 07:30:00\n02.09.2026 00:20:00".to_owned(),
+        ))
+    }
+
+    #[test]
+    fn test_csv() {
+        let timesheet = sample_timesheet();
+        let september = Period::month(2026, 9);
+
+        assert_eq!(
+            csv(&timesheet, Some(&september), ';', true),
+            "date;hours\n2026-09-01;7.50\n2026-09-02;0.33\n"
+        );
+        assert_eq!(
+            csv(&timesheet, None, ',', false),
+            "date,hours\n2026-08-31,08:00:00\n2026-09-01,07:30:00\n2026-09-02,00:20:00\n"
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        let timesheet = sample_timesheet();
+
+        let entries: serde_json::Value = serde_json::from_str(&json(&timesheet, None)).unwrap();
+
+        assert_eq!(entries.as_array().unwrap().len(), 3);
+        assert_eq!(entries[1]["date"], "2026-09-01");
+        assert_eq!(entries[1]["duration"], "07:30:00");
+        assert_eq!(entries[1]["seconds"], 27000);
+    }
+
+    #[test]
+    fn test_icalendar() {
+        let start = FixedOffset::east(2 * 3600).ymd(2026, 9, 1).and_hms(9, 0, 0);
+        let sessions = vec![
+            RecordedSession {
+                project: Some("acme, inc".to_owned()),
+                billable: true,
+                start,
+                end: start + Duration::hours(8),
+                pauses: vec![(start + Duration::hours(3), start + Duration::hours(4))],
+                open: false,
+            },
+            RecordedSession {
+                project: None,
+                billable: true,
+                start: start + Duration::hours(9),
+                end: start + Duration::hours(10),
+                pauses: Vec::new(),
+                open: true,
+            },
+        ];
+
+        let calendar = icalendar(&sessions, None);
+
+        assert!(calendar.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(calendar.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!(calendar.matches("BEGIN:VEVENT").count(), 1);
+        assert!(calendar.contains(&format!("DTSTART:{}\r\n", format_time(start))));
+        assert!(calendar.contains("SUMMARY:acme\\, inc\r\n"));
+        assert!(calendar.contains("DESCRIPTION:07:00:00 tracked\r\n"));
+    }
+
+    #[test]
+    fn test_icalendar_long_summary() {
+        let start = FixedOffset::east(2 * 3600).ymd(2026, 9, 1).and_hms(9, 0, 0);
+        let project = format!("Relaunch der Website für {}", "Müller & Söhne ".repeat(5));
+        let sessions = vec![RecordedSession {
+            project: Some(project.clone()),
+            billable: true,
+            start,
+            end: start + Duration::hours(2),
+            pauses: Vec::new(),
+            open: false,
+        }];
+
+        let calendar = icalendar(&sessions, None);
+
+        assert!(calendar.lines().all(|line| line.len() <= 76));
+        assert!(calendar.contains("\r\n "));
+        let unfolded = calendar.replace("\r\n ", "");
+        assert!(unfolded.contains(&format!("SUMMARY:{}\r\n", project)));
+    }
+}
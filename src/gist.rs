@@ -5,12 +5,16 @@ use reqwest::Client;
 use serde::Deserialize;
 use serde_json::json;
 
+use crate::credentials::Secret;
 use crate::report::Report;
 
-const API_ROOT: &'static str = "https://api.github.com";
+const API_ROOT: &str = "https://api.github.com";
+const FILE_NAME: &str = "hours";
+const PLACEHOLDER_FILE_NAME: &str = "README.md";
+const PLACEHOLDER_CONTENT: &str = "Hours worked, synced by `hours`.";
 
 pub struct GistClient {
-    api_key: String,
+    api_key: Secret,
     gist_id: String,
     client: Client,
 }
@@ -20,6 +24,11 @@ pub struct GetReport {
     pub last_updated: DateTime<FixedOffset>,
 }
 
+#[derive(Debug, Deserialize)]
+struct CreatedGistModel {
+    id: String,
+}
+
 #[derive(Debug, Deserialize)]
 struct GistModel {
     updated_at: String,
@@ -32,7 +41,7 @@ struct GistFileModel {
 }
 
 impl GistClient {
-    pub fn new(api_key: String, gist_id: String) -> Self {
+    pub fn new(api_key: Secret, gist_id: String) -> Self {
         let client = Client::new();
         Self {
             client,
@@ -41,18 +50,46 @@ impl GistClient {
         }
     }
 
+    /// Creates a private gist holding `report` and returns its ID.
+    ///
+    /// Gists can't be created empty, so an empty report is replaced with a placeholder file; the
+    /// `hours` file is then added on the first sync that has something to upload.
+    pub async fn create(api_key: &Secret, report: &Report) -> String {
+        let files = if report.0.trim().is_empty() {
+            json!({ PLACEHOLDER_FILE_NAME: { "content": PLACEHOLDER_CONTENT } })
+        } else {
+            json!({ FILE_NAME: { "content": report.0 } })
+        };
+        let res = Client::new()
+            .post(format!("{}/gists", API_ROOT))
+            .json(&json!({
+                "description": "hours",
+                "public": false,
+                "files": files
+            }))
+            .header("User-Agent", "whatever")
+            .header("Authorization", format!("Token {}", api_key.expose()))
+            .send()
+            .await
+            .unwrap()
+            .error_for_status()
+            .unwrap();
+        let gist: CreatedGistModel = res.json().await.unwrap();
+        gist.id
+    }
+
     pub async fn update(&self, report: &Report) {
         self.client
             .patch(format!("{}/gists/{}", API_ROOT, self.gist_id))
             .json(&json!({
                 "files": {
-                    "hours": {
+                    FILE_NAME: {
                         "content": report.0
                     }
                 }
             }))
             .header("User-Agent", "whatever")
-            .header("Authorization", format!("Token {}", self.api_key))
+            .header("Authorization", format!("Token {}", self.api_key.expose()))
             .send()
             .await
             .unwrap();
@@ -63,13 +100,18 @@ impl GistClient {
             .client
             .get(format!("{}/gists/{}", API_ROOT, self.gist_id))
             .header("User-Agent", "whatever")
-            .header("Authorization", format!("Token {}", self.api_key))
+            .header("Authorization", format!("Token {}", self.api_key.expose()))
             .send()
             .await
             .unwrap();
         let gist: GistModel = res.json().await.unwrap();
+        let content = gist
+            .files
+            .get(FILE_NAME)
+            .map(|file| file.content.clone())
+            .unwrap_or_default();
         GetReport {
-            report: Report(gist.files["hours"].content.clone()),
+            report: Report(content),
             last_updated: DateTime::parse_from_rfc3339(&gist.updated_at).unwrap(),
         }
     }
@@ -0,0 +1,392 @@
+use std::fmt;
+
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, NaiveDateTime, Utc};
+use serde::Deserialize;
+
+use crate::timesheet::Timesheet;
+use crate::{clock, util};
+
+/// Reads the export of another time tracker.
+pub trait Importer {
+    /// The name used on the command line.
+    fn name(&self) -> &'static str;
+
+    /// The hours worked per day in `content`. A day may appear several times.
+    fn import(&self, content: &str) -> Result<Vec<(NaiveDate, Duration)>, ImportError>;
+}
+
+/// All importers, in the order they're listed in the usage.
+pub fn importers() -> Vec<Box<dyn Importer>> {
+    vec![
+        Box::new(Toggl),
+        Box::new(Clockify),
+        Box::new(Timewarrior),
+        Box::new(PlainCsv),
+    ]
+}
+
+pub fn importer(name: &str) -> Option<Box<dyn Importer>> {
+    importers()
+        .into_iter()
+        .find(|importer| importer.name() == name)
+}
+
+#[derive(Debug)]
+pub struct ImportError {
+    /// Line 1 is the header of CSV files.
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl ImportError {
+    fn new(line: Option<usize>, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {}", line, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// How imported hours are combined with the hours already in the timesheet.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    /// Imported hours are added to the days' existing hours.
+    Merge,
+    /// Days in the import get exactly the imported hours.
+    Replace,
+}
+
+/// The timesheet after importing `entries` into `timesheet`.
+pub fn apply(timesheet: &Timesheet, entries: &[(NaiveDate, Duration)], mode: Mode) -> Timesheet {
+    let mut imported = Timesheet::default();
+    for (date, duration) in entries {
+        imported.add_hours(date, duration);
+    }
+    let mut result = timesheet.without_hours();
+    for (date, duration) in timesheet.entries() {
+        if mode == Mode::Merge || imported.get_hours(&date) == Duration::zero() {
+            result.add_hours(&date, &duration);
+        }
+    }
+    for (date, duration) in imported.entries() {
+        result.add_hours(&date, &duration);
+    }
+    result
+}
+
+/// The days that change from `before` to `after`, one per line, and the change of the total.
+pub fn summary(before: &Timesheet, after: &Timesheet) -> String {
+    let mut lines = Vec::new();
+    for date in before.dates_with(after) {
+        let (old, new) = (before.get_hours(&date), after.get_hours(&date));
+        if old.num_seconds() != new.num_seconds() {
+            lines.push(format!(
+                "{} {} -> {}",
+                date.format("%d.%m.%Y"),
+                util::format_duration(old),
+                util::format_duration(new)
+            ));
+        }
+    }
+    let total = |timesheet: &Timesheet| {
+        timesheet
+            .entries()
+            .fold(Duration::zero(), |acc, (_, duration)| acc + duration)
+    };
+    lines.push(format!(
+        "{} day{} changed, total {} -> {}",
+        lines.len(),
+        if lines.len() == 1 { "" } else { "s" },
+        util::format_duration(total(before)),
+        util::format_duration(total(after))
+    ));
+    lines.join("\n")
+}
+
+/// Toggl Track's detailed report as CSV.
+struct Toggl;
+
+impl Importer for Toggl {
+    fn name(&self) -> &'static str {
+        "toggl"
+    }
+
+    fn import(&self, content: &str) -> Result<Vec<(NaiveDate, Duration)>, ImportError> {
+        read_csv(content, |row| {
+            let date = parse_date(row.get("start date")?)?;
+            let duration = parse_hours(row.get("duration")?)?;
+            Ok((date, duration))
+        })
+    }
+}
+
+/// Clockify's detailed report as CSV.
+struct Clockify;
+
+impl Importer for Clockify {
+    fn name(&self) -> &'static str {
+        "clockify"
+    }
+
+    fn import(&self, content: &str) -> Result<Vec<(NaiveDate, Duration)>, ImportError> {
+        read_csv(content, |row| {
+            let date = parse_date(row.get("start date")?)?;
+            let duration = match row.get("duration (h)") {
+                Ok(duration) => parse_hours(duration)?,
+                Err(_) => parse_hours(row.get("duration (decimal)")?)?,
+            };
+            Ok((date, duration))
+        })
+    }
+}
+
+/// The output of `timew export`.
+struct Timewarrior;
+
+#[derive(Deserialize)]
+struct Interval {
+    start: String,
+    end: Option<String>,
+}
+
+impl Importer for Timewarrior {
+    fn name(&self) -> &'static str {
+        "timewarrior"
+    }
+
+    fn import(&self, content: &str) -> Result<Vec<(NaiveDate, Duration)>, ImportError> {
+        let intervals: Vec<Interval> = serde_json::from_str(content)
+            .map_err(|e| ImportError::new(Some(e.line()), e.to_string()))?;
+        let parse = |time: &str| {
+            NaiveDateTime::parse_from_str(time, "%Y%m%dT%H%M%SZ")
+                .map(|time| DateTime::<FixedOffset>::from(DateTime::<Utc>::from_utc(time, Utc)))
+                .map_err(|_| ImportError::new(None, format!("invalid time \"{}\"", time)))
+        };
+        let mut entries = Vec::new();
+        // Intervals without an end are still being tracked.
+        for interval in intervals {
+            if let Some(end) = interval.end {
+                let start = parse(&interval.start)?;
+                entries.push((clock::date(start), parse(&end)? - start));
+            }
+        }
+        Ok(entries)
+    }
+}
+
+/// A CSV file with a date and the hours worked on it per row, separated by `,` or `;`. The hours
+/// can be `HH:MM:SS`, `HH:MM` or decimal. A header row is optional.
+struct PlainCsv;
+
+impl Importer for PlainCsv {
+    fn name(&self) -> &'static str {
+        "csv"
+    }
+
+    fn import(&self, content: &str) -> Result<Vec<(NaiveDate, Duration)>, ImportError> {
+        let delimiter = match content.lines().next() {
+            Some(line) if line.contains(';') => b';',
+            _ => b',',
+        };
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .delimiter(delimiter)
+            .trim(csv::Trim::All)
+            .from_reader(content.as_bytes());
+        let mut entries = Vec::new();
+        for (i, record) in reader.records().enumerate() {
+            let line = Some(i + 1);
+            let record = record.map_err(|e| ImportError::new(line, e.to_string()))?;
+            let (date, hours) = match (record.get(0), record.get(1), record.len()) {
+                (Some(date), Some(hours), 2) => (date, hours),
+                _ => return Err(ImportError::new(line, "expected a date and hours")),
+            };
+            match (parse_date(date), parse_hours(hours)) {
+                (Ok(date), Ok(duration)) => entries.push((date, duration)),
+                // A header, where neither field is a date or hours.
+                (Err(_), Err(_)) if i == 0 => {}
+                (Err(e), _) | (_, Err(e)) => return Err(ImportError::new(line, e.message)),
+            }
+        }
+        Ok(entries)
+    }
+}
+
+/// A row of a CSV file with a header.
+struct Row<'a> {
+    headers: &'a csv::StringRecord,
+    record: csv::StringRecord,
+}
+
+impl Row<'_> {
+    /// The value in the column named `column`, ignoring case.
+    fn get(&self, column: &str) -> Result<&str, ImportError> {
+        self.headers
+            .iter()
+            .position(|header| header.eq_ignore_ascii_case(column))
+            .and_then(|i| self.record.get(i))
+            .ok_or_else(|| ImportError::new(None, format!("no \"{}\" column", column)))
+    }
+}
+
+fn read_csv(
+    content: &str,
+    parse_row: impl Fn(&Row) -> Result<(NaiveDate, Duration), ImportError>,
+) -> Result<Vec<(NaiveDate, Duration)>, ImportError> {
+    // Spreadsheet tools like to add a byte order mark.
+    let content = content.trim_start_matches('\u{feff}');
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(content.as_bytes());
+    let headers = reader
+        .headers()
+        .map_err(|e| ImportError::new(Some(1), e.to_string()))?
+        .clone();
+    let mut entries = Vec::new();
+    for (i, record) in reader.records().enumerate() {
+        let line = Some(i + 2);
+        let record = record.map_err(|e| ImportError::new(line, e.to_string()))?;
+        let row = Row {
+            headers: &headers,
+            record,
+        };
+        entries.push(parse_row(&row).map_err(|e| ImportError { line, ..e })?);
+    }
+    Ok(entries)
+}
+
+fn parse_date(s: &str) -> Result<NaiveDate, ImportError> {
+    ["%Y-%m-%d", "%d.%m.%Y", "%m/%d/%Y"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(s, format).ok())
+        .ok_or_else(|| ImportError::new(None, format!("invalid date \"{}\"", s)))
+}
+
+fn parse_hours(s: &str) -> Result<Duration, ImportError> {
+    let duration = match s.matches(':').count() {
+        2 => util::parse_duration(s),
+        1 => util::parse_duration(&format!("{}:00", s)),
+        _ => s
+            .replace(',', ".")
+            .parse::<f64>()
+            .ok()
+            .filter(|hours| hours.is_finite() && *hours >= 0.0)
+            .map(|hours| Duration::seconds((hours * 3600.0).round() as i64)),
+    };
+    duration.ok_or_else(|| ImportError::new(None, format!("invalid duration \"{}\"", s)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::report::Report;
+
+    use super::*;
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd(2026, month, day)
+    }
+
+    #[test]
+    fn test_toggl() {
+        let content = "\u{feff}User,Email,Client,Project,Task,Description,Billable,Start date,Start time,End date,End time,Duration,Tags,Amount ()
+Jane,jane@example.com,,Acme,,\"Review, part 1\",Yes,2026-09-01,09:00:00,2026-09-01,12:30:00,03:30:00,,
+Jane,jane@example.com,,Acme,,Review,Yes,2026-09-01,13:00:00,2026-09-01,14:00:00,01:00:00,,
+";
+
+        let entries = importer("toggl").unwrap().import(content).unwrap();
+
+        assert_eq!(
+            entries,
+            vec![
+                (date(9, 1), Duration::minutes(210)),
+                (date(9, 1), Duration::hours(1))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_clockify() {
+        let content = "Project,Client,Description,Task,User,Group,Email,Tags,Billable,Start Date,Start Time,End Date,End Time,Duration (h),Duration (decimal)
+Acme,,Review,,Jane,,jane@example.com,,Yes,09/02/2026,09:00:00 AM,09/02/2026,11:15:00 AM,02:15:00,2.25
+";
+
+        let entries = importer("clockify").unwrap().import(content).unwrap();
+
+        assert_eq!(entries, vec![(date(9, 2), Duration::minutes(135))]);
+    }
+
+    #[test]
+    fn test_timewarrior() {
+        let content = r#"[
+{"id":2,"start":"20260903T080000Z","end":"20260903T093000Z","tags":["acme"]},
+{"id":1,"start":"20260904T080000Z"}
+]"#;
+
+        let entries = importer("timewarrior").unwrap().import(content).unwrap();
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].1, Duration::minutes(90));
+    }
+
+    #[test]
+    fn test_plain_csv() {
+        let content = "date;hours\n2026-09-01;7,5\n02.09.2026;06:45\n2026-09-03;01:00:30\n";
+
+        let entries = importer("csv").unwrap().import(content).unwrap();
+
+        assert_eq!(
+            entries,
+            vec![
+                (date(9, 1), Duration::minutes(450)),
+                (date(9, 2), Duration::minutes(405)),
+                (date(9, 3), Duration::seconds(3630)),
+            ]
+        );
+        let error = importer("csv")
+            .unwrap()
+            .import("2026-09-01,07:30\n2026-09-02,lots\n")
+            .unwrap_err();
+        assert_eq!(error.to_string(), "line 2: invalid duration \"lots\"");
+        // A first row with a bad date isn't taken for a header.
+        let error = importer("csv")
+            .unwrap()
+            .import("31.13.2026,8\n2026-09-02,07:30\n")
+            .unwrap_err();
+        assert_eq!(error.line, Some(1));
+    }
+
+    #[test]
+    fn test_apply() {
+        let timesheet = Timesheet::parse_report(&Report(
+            "01.09.2026 02:00:00\n02.09.2026 03:00:00".to_owned(),
+        ));
+        let entries = vec![
+            (date(9, 2), Duration::hours(1)),
+            (date(9, 3), Duration::hours(4)),
+        ];
+
+        let merged = apply(&timesheet, &entries, Mode::Merge);
+        let replaced = apply(&timesheet, &entries, Mode::Replace);
+
+        let hours = |timesheet: &Timesheet| -> Vec<i64> {
+            timesheet.entries().map(|(_, d)| d.num_hours()).collect()
+        };
+        assert_eq!(hours(&merged), vec![2, 4, 4]);
+        assert_eq!(hours(&replaced), vec![2, 1, 4]);
+        assert_eq!(
+            summary(&timesheet, &replaced),
+            "02.09.2026 03:00:00 -> 01:00:00
+03.09.2026 00:00:00 -> 04:00:00
+2 days changed, total 05:00:00 -> 07:00:00"
+        );
+    }
+}
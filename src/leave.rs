@@ -0,0 +1,135 @@
+use chrono::{Datelike, Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::period::Period;
+use crate::remaining_work;
+use crate::timesheet::Timesheet;
+
+/// Why a day was taken off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LeaveKind {
+    Vacation,
+    Sick,
+    Holiday,
+    /// Time off in lieu of overtime.
+    Comp,
+}
+
+pub const LEAVE_KINDS: [LeaveKind; 4] = [
+    LeaveKind::Vacation,
+    LeaveKind::Sick,
+    LeaveKind::Holiday,
+    LeaveKind::Comp,
+];
+
+impl LeaveKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Vacation => "vacation",
+            Self::Sick => "sick",
+            Self::Holiday => "holiday",
+            Self::Comp => "comp",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        LEAVE_KINDS.iter().copied().find(|kind| kind.name() == s)
+    }
+
+    /// Whether the day counts towards the target. Comp days are paid with overtime instead.
+    pub fn counts(&self) -> bool {
+        *self != Self::Comp
+    }
+}
+
+/// How leave counts, from the settings.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LeaveSettings {
+    pub mode: LeaveMode,
+    /// Vacation days per calendar year.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vacation_days: Option<u32>,
+}
+
+impl LeaveSettings {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LeaveMode {
+    /// Days off lower the target by their share of it.
+    #[default]
+    ReduceTarget,
+    /// Days off count as if their share of the target was worked.
+    Credit,
+}
+
+/// A working day's share of the monthly target.
+pub fn daily_target(date: NaiveDate) -> Duration {
+    let month = Period::month(date.year(), date.month());
+    month.target_until(month.to) / month.working_days_until(month.to)
+}
+
+/// The working days taken off for vacation in `year`.
+pub fn vacation_taken(timesheet: &Timesheet, year: i32) -> u32 {
+    timesheet
+        .leave()
+        .filter(|&(date, kind)| {
+            kind == LeaveKind::Vacation
+                && date.year() == year
+                && remaining_work::is_working_day(date.weekday())
+        })
+        .count() as u32
+}
+
+/// Like `Vacation 2026: 12 of 30 days taken, 18 left`.
+pub fn vacation_line(timesheet: &Timesheet, year: i32, allowance: u32) -> String {
+    let taken = vacation_taken(timesheet, year);
+    format!(
+        "Vacation {}: {} of {} days taken, {}",
+        year,
+        taken,
+        allowance,
+        if taken > allowance {
+            format!("{} over", taken - allowance)
+        } else {
+            format!("{} left", allowance - taken)
+        }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::report::Report;
+
+    use super::*;
+
+    #[test]
+    fn test_vacation() {
+        let timesheet = Timesheet::parse_report(&Report(
+            "
+14.09.2026 vacation
+15.09.2026 vacation
+19.09.2026 vacation
+21.09.2026 sick
+"
+            .to_owned(),
+        ));
+
+        // The 19th is a Saturday.
+        assert_eq!(vacation_taken(&timesheet, 2026), 2);
+        assert_eq!(
+            vacation_line(&timesheet, 2026, 30),
+            "Vacation 2026: 2 of 30 days taken, 28 left"
+        );
+        assert_eq!(
+            daily_target(NaiveDate::from_ymd(2026, 9, 14)),
+            Duration::hours(160) / 22
+        );
+    }
+}
@@ -0,0 +1,139 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::os::unix::io::AsRawFd;
+
+use chrono::{DateTime, Duration, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::paths;
+
+/// The process currently tracking time on the timesheet, as recorded in the lock file.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Instance {
+    pub pid: u32,
+    pub started_at: DateTime<Local>,
+    /// Hours already recorded for the day the session started on, before it started.
+    pub seconds_before_start: i64,
+    /// Whether the process is `hours daemon`, which is controlled through its socket.
+    #[serde(default)]
+    pub daemon: bool,
+}
+
+impl Instance {
+    pub fn new(started_at: DateTime<Local>, before_start: Duration) -> Self {
+        Self {
+            pid: std::process::id(),
+            started_at,
+            seconds_before_start: before_start.num_seconds(),
+            daemon: false,
+        }
+    }
+
+    /// The hours recorded today so far, including the running session.
+    pub fn duration_for_today(&self) -> Duration {
+        Duration::seconds(self.seconds_before_start) + (Local::now() - self.started_at)
+    }
+}
+
+/// An advisory lock on the timesheet, held for as long as this value lives.
+pub struct InstanceLock {
+    file: File,
+}
+
+pub enum LockError {
+    /// Another process holds the lock. Its details are missing if it hasn't written them yet.
+    Locked(Option<Instance>),
+    Io(io::Error),
+}
+
+impl InstanceLock {
+    pub fn acquire() -> Result<Self, LockError> {
+        let mut file = open_lock_file().map_err(LockError::Io)?;
+        if !try_lock(&file).map_err(LockError::Io)? {
+            return Err(LockError::Locked(read_instance(&mut file)));
+        }
+        file.set_len(0).map_err(LockError::Io)?;
+        Ok(Self { file })
+    }
+
+    /// Writes the details other processes are shown when they find the timesheet locked.
+    pub fn record(&mut self, instance: &Instance) {
+        self.file.set_len(0).unwrap();
+        self.file.seek(SeekFrom::Start(0)).unwrap();
+        serde_json::to_writer(&mut self.file, instance).unwrap();
+        self.file.sync_all().unwrap();
+    }
+}
+
+/// Returns the process holding the lock, if there is one.
+pub fn running_instance() -> Option<Instance> {
+    let mut file = open_lock_file().ok()?;
+    match try_lock(&file) {
+        // Closing the file releases the lock again.
+        Ok(true) => None,
+        Ok(false) => read_instance(&mut file),
+        Err(_) => None,
+    }
+}
+
+fn open_lock_file() -> io::Result<File> {
+    let path = paths::lock_file();
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+}
+
+fn try_lock(file: &File) -> io::Result<bool> {
+    let res = unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) };
+    if res == 0 {
+        return Ok(true);
+    }
+    let e = io::Error::last_os_error();
+    if e.raw_os_error() == Some(libc::EWOULDBLOCK) {
+        Ok(false)
+    } else {
+        Err(e)
+    }
+}
+
+fn read_instance(file: &mut File) -> Option<Instance> {
+    let mut content = String::new();
+    file.read_to_string(&mut content).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_lock_is_exclusive() {
+        let dir = TempDir::new("lock");
+        std::fs::create_dir_all(dir.path()).unwrap();
+        let path = dir.join("hours.lock");
+        let open = || {
+            OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(&path)
+                .unwrap()
+        };
+
+        let first = open();
+        let second = open();
+        assert!(try_lock(&first).unwrap());
+        assert!(!try_lock(&second).unwrap());
+        drop(first);
+        assert!(try_lock(&second).unwrap());
+    }
+}
@@ -1,51 +1,587 @@
 use std::{
-    sync::mpsc::{self, Sender},
-    thread,
-    time::{self, Instant},
+    env, fs,
+    path::{Path, PathBuf},
+    process,
+    sync::{Arc, Mutex},
 };
 
-use chrono::{DateTime, Duration, FixedOffset, Local};
+use charts::Charts;
+use chrono::{DateTime, Datelike, FixedOffset, Local};
+use cli::Command;
+use credentials::Secret;
+use daemon::{ClientError, Request};
+use events::{Event, EventLog, ReconcileError};
+use export::Format;
 use gist::GistClient;
-use remaining_work::IncludeToday;
+use import::{Importer, Mode};
+use leave::{LeaveKind, LeaveMode};
+use lock::{Instance, InstanceLock, LockError};
+use period::Period;
+use pomodoro::Pomodoro;
+use remaining_work::{IncludeToday, WeeklyWork, Workday};
 use report::Report;
+use session::{Cancel, Session};
 use settings::Settings;
+use stats::Stats;
 use timesheet::Timesheet;
 
+mod billing;
+mod charts;
+mod cli;
+mod clock;
+mod compliance;
+mod credentials;
+mod daemon;
+mod events;
+mod export;
 mod gist;
+mod import;
+mod leave;
+mod lock;
+mod overtime;
+mod paths;
+mod pdf;
+mod period;
+mod pomodoro;
 mod remaining_work;
 mod report;
+mod session;
 mod settings;
+mod stats;
 mod terminal;
 mod timesheet;
 mod util;
 
 #[tokio::main(flavor = "current_thread")]
 async fn main() {
-    Report::commit_backup();
-    sync_gist().await;
+    let args = cli::parse_args();
+    match args.command {
+        Command::Track { pomodoro } => track(args.force, pomodoro).await,
+        Command::Init => init().await,
+        Command::EncryptKey => encrypt_key(),
+        Command::Attach => match lock::running_instance() {
+            Some(instance) => attach(instance),
+            None => println!("No hours process is running."),
+        },
+        Command::Daemon { foreground } => match acquire_lock() {
+            Some(instance_lock) => daemon::run(instance_lock, args.force, foreground),
+            None => process::exit(1),
+        },
+        Command::Control(request) => control(request, args.force).await,
+        Command::Export(format, period) => export(format, period),
+        Command::Import {
+            importer,
+            file,
+            mode,
+            dry_run,
+        } => import(importer.as_ref(), &file, mode, dry_run, args.force).await,
+        Command::Stats => stats(),
+        Command::Close(month) => close(&month, args.force).await,
+        Command::Compliance(month) => compliance(&month),
+        Command::ListLeave => list_leave(),
+        Command::Leave(kind, days) => take_leave(kind, &days, args.force).await,
+        Command::Bill(period) => bill(&period),
+        Command::Pdf { month, output } => pdf(&month, output),
+        Command::Report(period) => report(&period),
+    }
+}
+
+/// Locks the timesheet, or tells the user who holds the lock and returns `None`.
+fn acquire_lock() -> Option<InstanceLock> {
+    match InstanceLock::acquire() {
+        Ok(instance_lock) => Some(instance_lock),
+        Err(LockError::Locked(Some(instance))) if instance.daemon => {
+            println!(
+                "The hours daemon (PID {}) is running, use `hours start`, `hours stop` and \
+                 `hours status` to control it.",
+                instance.pid
+            );
+            None
+        }
+        Err(LockError::Locked(Some(instance))) => {
+            println!(
+                "Another hours process (PID {}) has been running since {}.",
+                instance.pid,
+                instance.started_at.format("%H:%M:%S on %d.%m.%Y")
+            );
+            if terminal::confirm("Attach to it?", true) {
+                attach(instance);
+            }
+            None
+        }
+        Err(LockError::Locked(None)) => {
+            println!("Another hours process is starting.");
+            None
+        }
+        Err(LockError::Io(e)) => panic!("Could not lock the timesheet: {}", e),
+    }
+}
+
+/// Runs the timer in the terminal until Ctrl+C, in Pomodoro mode if `pomodoro` is set.
+async fn track(force: bool, pomodoro: bool) {
+    let mut instance_lock = match acquire_lock() {
+        Some(instance_lock) => instance_lock,
+        None => return,
+    };
+    let settings = Settings::load();
+    let gist_client = GistClient::new(settings.api_key(), settings.gist_id.clone());
+    let log = EventLog::open();
+    log.prepare(force);
+    let recovered_session = session::recover_interrupted_session(&log, true);
+    sync_gist(&gist_client, force).await;
 
-    let start = Instant::now();
-    let duration_for_today = load_timesheet().get_hours(&Local::today());
-    let (handle, backup_cancel_sender) = write_backups_in_background(start);
+    let session = recovered_session.unwrap_or_else(|| Session::start(log.clone(), None, true));
+    // The session is booked on the day it started, so the countdown and the warnings are about
+    // that day, also when the timer keeps running past midnight.
+    let date = session.date;
+    let events = log.read();
+    let workday = Workday::on(date, &Timesheet::load(), &events, settings.lunch_break());
+    let compliance = settings.compliance.and_then(|rules| {
+        let start = events::day_start(&events, date)?;
+        let previous_end = compliance::last_end_before(&events::sessions(&events), date);
+        Some((rules, start, previous_end))
+    });
+    let mut warned = 0;
+    let session = Arc::new(Mutex::new(Some(session)));
+    let duration_for_today = Timesheet::load().get_hours(&date);
+    instance_lock.record(&Instance::new(Local::now(), duration_for_today));
+    let (handle, heartbeat_cancel_sender) =
+        session::write_heartbeats_in_background(session.clone(), settings.suspended_time);
+    let mut pomodoro = pomodoro.then(|| Pomodoro::start(settings.pomodoro, clock::now()));
     terminal::init();
     while !terminal::ctrlc() {
-        let duration = Duration::from_std(start.elapsed()).unwrap() + duration_for_today;
-        terminal::draw_duration(duration);
+        let suspend = session
+            .lock()
+            .unwrap()
+            .as_mut()
+            .unwrap()
+            .take_unconfirmed_suspend();
+        if let Some(suspend) = suspend {
+            let count = terminal::ask(&format!(
+                "The machine was suspended from {} to {} ({}). Count that time?",
+                suspend.from.format("%H:%M:%S"),
+                suspend.to.format("%H:%M:%S"),
+                util::format_duration(suspend.duration())
+            ));
+            if count {
+                session
+                    .lock()
+                    .unwrap()
+                    .as_mut()
+                    .unwrap()
+                    .count_suspend(&suspend);
+            }
+        }
+        if let Some(pomodoro) = pomodoro.as_mut() {
+            if let Some(phase) = pomodoro.advance(clock::now()) {
+                let mut session = session.lock().unwrap();
+                let session = session.as_mut().unwrap();
+                if phase.is_break() {
+                    session.complete_pomodoro();
+                    session.pause();
+                } else {
+                    session.resume();
+                }
+                terminal::bell();
+            }
+        }
+        let elapsed = session.lock().unwrap().as_ref().unwrap().elapsed();
+        let now = clock::now();
+        let notes: Vec<_> = pomodoro
+            .as_ref()
+            .map(|pomodoro| pomodoro.status(now))
+            .into_iter()
+            .chain(
+                workday
+                    .as_ref()
+                    .map(|workday| workday.countdown(elapsed + duration_for_today, now)),
+            )
+            .collect();
+        let warnings = compliance.map_or(Vec::new(), |(rules, start, previous_end)| {
+            rules.warnings(elapsed + duration_for_today, start, previous_end, now)
+        });
+        // The bell rings once for every new warning.
+        if warnings.len() > warned {
+            terminal::bell();
+        }
+        warned = warnings.len();
+        let notes: Vec<_> = notes.into_iter().chain(warnings).collect();
+        let note = (!notes.is_empty()).then(|| notes.join(", "));
+        terminal::draw_duration(elapsed + duration_for_today, note.as_deref());
     }
     terminal::quit();
     println!();
-    backup_cancel_sender.send(Cancel).unwrap();
+    heartbeat_cancel_sender.send(Cancel).unwrap();
     handle.join().unwrap();
 
-    Report::commit_backup();
-    sync_gist().await;
+    session.lock().unwrap().take().unwrap().book();
+    sync_gist(&gist_client, force).await;
+    drop(instance_lock);
     show_remaining_work();
 }
 
-async fn sync_gist() {
-    let report = Report::load();
+async fn control(request: Request, force: bool) {
+    let syncs = matches!(
+        request,
+        Request::Start { .. } | Request::Stop | Request::Switch { .. } | Request::Shutdown
+    );
+    match daemon::send(&request) {
+        Ok(Ok(message)) => println!("{}", message),
+        Ok(Err(message)) => util::exit_with_error(message),
+        Err(ClientError::NotRunning) => {
+            util::exit_with_error("The hours daemon isn't running, start it with `hours daemon`.")
+        }
+        Err(ClientError::Io(e)) => {
+            util::exit_with_error(format!("Could not talk to the hours daemon: {}", e))
+        }
+    }
+    if syncs {
+        let settings = Settings::load();
+        let gist_client = GistClient::new(settings.api_key(), settings.gist_id.clone());
+        sync_gist(&gist_client, force).await;
+    }
+    if let Request::Stop | Request::Shutdown = request {
+        show_remaining_work();
+    }
+}
+
+/// Closes `month` with its hours after syncing, so they can't change without `--force`.
+async fn close(month: &Period, force: bool) {
+    if month.to >= clock::today() {
+        util::exit_with_error(format!("{} isn't over yet.", month.name));
+    }
+    let instance_lock = match acquire_lock() {
+        Some(instance_lock) => instance_lock,
+        None => return,
+    };
+    let settings = Settings::load();
+    let gist_client = GistClient::new(settings.api_key(), settings.gist_id.clone());
+    let log = EventLog::open();
+    log.prepare(force);
+    sync_gist(&gist_client, force).await;
+    if let Some(session) = log
+        .open_session()
+        .filter(|session| month.contains(session.date()))
+    {
+        util::exit_with_error(format!(
+            "The session started at {} was interrupted, run `hours` to book it first.",
+            session.start.format("%d.%m.%Y %H:%M")
+        ));
+    }
+    let mut timesheet = Timesheet::load();
+    let (year, number) = (month.from.year(), month.from.month());
+    if timesheet.closing(year, number).is_some() && !force {
+        util::exit_with_error(format!(
+            "{} is already closed, use --force to close it again with its current hours.",
+            month.name
+        ));
+    }
+    let closing = timesheet.close(year, number);
+    log.append(&Event::MonthClosed {
+        at: clock::now(),
+        year,
+        month: number,
+        checksum: closing.checksum.clone(),
+    });
+    log.save_projection();
+    sync_gist(&gist_client, force).await;
+    drop(instance_lock);
+    println!(
+        "Closed {} with {} ({}).",
+        month.name,
+        util::format_duration(month.hours(&timesheet)),
+        closing.checksum
+    );
+}
+
+fn report(period: &Period) {
+    let timesheet = Timesheet::load();
+    let today = clock::today();
+    let settings = Settings::try_load();
+    let leave = settings
+        .as_ref()
+        .map(|settings| settings.leave)
+        .unwrap_or_default();
+    println!("{}", period.report(&timesheet, today, &leave));
+    if let Some(allowance) = leave.vacation_days {
+        println!(
+            "{}",
+            leave::vacation_line(&timesheet, period.to.year(), allowance)
+        );
+    }
+    let overtime = settings.and_then(|settings| settings.overtime);
+    let until = period.to.min(today);
+    if let Some(overtime) = overtime.filter(|overtime| overtime.since <= until) {
+        println!("{}", overtime.balance(&timesheet, until).summary());
+    }
+}
+
+fn compliance(month: &Period) {
+    let rules = Settings::try_load()
+        .and_then(|settings| settings.compliance)
+        .unwrap_or_default();
+    let events = EventLog::open().read();
+    println!("{}", rules.report(&Timesheet::load(), &events, month));
+}
+
+fn list_leave() {
+    let timesheet = Timesheet::load();
+    let year = clock::today().year();
+    let mut days_off = timesheet
+        .leave()
+        .filter(|(date, _)| date.year() == year)
+        .peekable();
+    if days_off.peek().is_none() {
+        println!("No days off in {}.", year);
+    }
+    for (date, kind) in days_off {
+        println!(
+            "{} {} {}",
+            date.weekday(),
+            date.format("%d.%m.%Y"),
+            kind.name()
+        );
+    }
+    if let Some(allowance) = Settings::try_load().and_then(|settings| settings.leave.vacation_days)
+    {
+        println!("{}", leave::vacation_line(&timesheet, year, allowance));
+    }
+}
+
+/// Records the working days of `days` as taken off, or with `None`, as normal days again.
+async fn take_leave(kind: Option<LeaveKind>, days: &Period, force: bool) {
+    let instance_lock = match acquire_lock() {
+        Some(instance_lock) => instance_lock,
+        None => return,
+    };
+    let settings = Settings::load();
+    let gist_client = GistClient::new(settings.api_key(), settings.gist_id.clone());
+    let log = EventLog::open();
+    log.prepare(force);
+    sync_gist(&gist_client, force).await;
+    let mut timesheet = Timesheet::load();
+    // A single day is taken as given, in a range only the working days are.
+    let changed: Vec<_> = days
+        .days()
+        .filter(|date| days.from == days.to || remaining_work::is_working_day(date.weekday()))
+        .filter(|date| timesheet.get_leave(*date) != kind)
+        .collect();
+    for date in &changed {
+        timesheet.set_leave(*date, kind);
+    }
+    if let Err(e) = log.reconcile(&timesheet, "leave", force) {
+        util::exit_with_error(format!("{}, use --force to change its days off anyway.", e));
+    }
+    log.save_projection();
+    sync_gist(&gist_client, force).await;
+    drop(instance_lock);
+    let count = match changed.len() {
+        1 => "1 day".to_owned(),
+        count => format!("{} days", count),
+    };
+    match kind {
+        Some(kind) => println!("Recorded {} as {}.", count, kind.name()),
+        None => println!("Removed the leave of {}.", count),
+    }
+    if let Some(allowance) = settings.leave.vacation_days {
+        println!(
+            "{}",
+            leave::vacation_line(&timesheet, days.from.year(), allowance)
+        );
+    }
+}
+
+fn stats() {
+    let timesheet = Timesheet::load();
+    let charts = Charts::new(&timesheet, clock::today(), terminal::is_color_output());
+    println!(
+        "{}\n\n{}\n\n{}\n\n{}",
+        charts.heatmap(),
+        charts.weekly_bars(),
+        charts.month_sparkline(),
+        Stats::new(&timesheet, clock::today()).summary()
+    );
+}
+
+fn bill(period: &Period) {
+    let billing = Settings::load().billing;
+    let lines = billing.summary(&EventLog::open().read(), period);
+    println!("{}", billing.render(&lines, period));
+}
+
+fn pdf(month: &Period, output: Option<PathBuf>) {
+    let settings = Settings::load();
+    let pdf = pdf::timesheet(
+        &Timesheet::load(),
+        month,
+        &settings.pdf,
+        &settings.report_format,
+    );
+    let output =
+        output.unwrap_or_else(|| PathBuf::from(month.from.format("hours-%Y-%m.pdf").to_string()));
+    if let Err(err) = util::write_atomic(&output, pdf) {
+        util::exit_with_error(format!("Could not write {}: {}", output.display(), err));
+    }
+    println!("Wrote {}.", output.display());
+}
+
+fn export(format: Format, period: Option<Period>) {
+    let output = match format {
+        Format::Csv { delimiter, decimal } => {
+            export::csv(&Timesheet::load(), period.as_ref(), delimiter, decimal)
+        }
+        Format::Json => export::json(&Timesheet::load(), period.as_ref()),
+        Format::ICalendar => {
+            export::icalendar(&events::sessions(&EventLog::open().read()), period.as_ref())
+        }
+    };
+    print!("{}", output);
+}
+
+async fn import(importer: &dyn Importer, file: &Path, mode: Mode, dry_run: bool, force: bool) {
+    let entries = fs::read_to_string(file)
+        .map_err(|e| e.to_string())
+        .and_then(|content| importer.import(&content).map_err(|e| e.to_string()))
+        .unwrap_or_else(|e| {
+            util::exit_with_error(format!("Could not import \"{}\", {}.", file.display(), e))
+        });
+    if dry_run {
+        let timesheet = Timesheet::load();
+        let imported = import::apply(&timesheet, &entries, mode);
+        println!("{}", import::summary(&timesheet, &imported));
+        if let Some(e) = timesheet.check_closed(&imported).err().filter(|_| !force) {
+            println!("But {}, so the import needs --force.", e);
+        }
+        return;
+    }
+
+    let instance_lock = match acquire_lock() {
+        Some(instance_lock) => instance_lock,
+        None => return,
+    };
     let settings = Settings::load();
-    let gist_client = GistClient::new(settings.api_key.clone(), settings.gist_id.clone());
+    let gist_client = GistClient::new(settings.api_key(), settings.gist_id.clone());
+    let log = EventLog::open();
+    log.prepare(force);
+    sync_gist(&gist_client, force).await;
+    let timesheet = Timesheet::load();
+    let imported = import::apply(&timesheet, &entries, mode);
+    if let Some(e) = timesheet.check_closed(&imported).err().filter(|_| !force) {
+        util::exit_with_error(format!(
+            "Could not import \"{}\", {}. Use --force to import it anyway.",
+            file.display(),
+            e
+        ));
+    }
+    println!("{}", import::summary(&timesheet, &imported));
+    if !terminal::confirm("Write these changes?", true) {
+        return;
+    }
+    // Closed months were checked above.
+    log.reconcile(
+        &imported,
+        &format!("imported from {}", importer.name()),
+        true,
+    )
+    .unwrap();
+    log.save_projection();
+    sync_gist(&gist_client, force).await;
+    drop(instance_lock);
+}
+
+/// Shows the timer of another process until it stops or Ctrl+C is pressed.
+fn attach(instance: Instance) {
+    println!("Attached to PID {}, press Ctrl+C to detach.", instance.pid);
+    terminal::init();
+    while !terminal::ctrlc() {
+        if lock::running_instance().map(|running| running.pid) != Some(instance.pid) {
+            break;
+        }
+        terminal::draw_duration(instance.duration_for_today(), None);
+    }
+    terminal::quit();
+    println!();
+}
+
+async fn init() {
+    if Settings::exists() {
+        println!(
+            "Settings file \"{}\" already exists, remove it to set up a new gist.",
+            paths::config_file().to_str().unwrap()
+        );
+        return;
+    }
+    let mut settings = Settings::default();
+    let api_key = match env::var(credentials::DEFAULT_ENV_VAR) {
+        Ok(api_key) => {
+            println!("Using the API key from {}.", credentials::DEFAULT_ENV_VAR);
+            Secret::new(api_key)
+        }
+        Err(_) => {
+            println!(
+                "Create a GitHub token with the \"gist\" scope at https://github.com/settings/tokens."
+            );
+            let api_key = Secret::new(terminal::prompt_secret("Token: ").trim().to_owned());
+            store_api_key(&mut settings, &api_key);
+            api_key
+        }
+    };
+    Report::commit_backup();
+    let report = Report::load();
+    if !report.0.trim().is_empty() {
+        println!("Uploading existing hours from the local file.");
+    }
+    settings.gist_id = GistClient::create(&api_key, &report).await;
+    settings.save();
+    println!(
+        "Created gist {}, settings written to \"{}\".",
+        settings.gist_id,
+        paths::config_file().to_str().unwrap()
+    );
+}
+
+fn encrypt_key() {
+    let mut settings = Settings::load();
+    let api_key = settings.api_key();
+    settings.api_key = None;
+    settings.api_key_env = None;
+    settings.api_key_command = None;
+    settings.api_key_file = None;
+    store_api_key(&mut settings, &api_key);
+    if settings.api_key_file.is_none() {
+        eprintln!("No passphrase given, nothing to encrypt.");
+        process::exit(1);
+    }
+    settings.save();
+    println!(
+        "Settings in \"{}\" updated.",
+        paths::config_file().to_str().unwrap()
+    );
+}
+
+/// Asks for a passphrase and stores the API key encrypted next to the settings file, or in the
+/// settings file itself if no passphrase is given.
+fn store_api_key(settings: &mut Settings, api_key: &Secret) {
+    let passphrase = terminal::prompt_secret(
+        "Passphrase to encrypt the token (leave empty to store it in plain text): ",
+    );
+    if passphrase.is_empty() {
+        settings.api_key = Some(api_key.clone());
+        return;
+    }
+    if terminal::prompt_secret("Repeat the passphrase: ") != passphrase {
+        eprintln!("The passphrases don't match.");
+        process::exit(1);
+    }
+    let path = paths::config_file().with_file_name("hours.key");
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    credentials::write_encrypted_file(&path, api_key, &passphrase);
+    println!("Encrypted token written to \"{}\".", path.to_str().unwrap());
+    settings.api_key_file = Some(path);
+}
+
+async fn sync_gist(gist_client: &GistClient, force: bool) {
+    let report = Report::load();
     let res = gist_client.get().await;
     if report.0.trim() != res.report.0.trim() {
         if should_update_local_file_from_gist(res.last_updated) {
@@ -53,7 +589,22 @@ async fn sync_gist() {
                 "Updating local file from gist. New content:\n{}",
                 res.report.0.trim()
             );
-            res.report.save();
+            let log = EventLog::open();
+            match log.reconcile_report(&res.report, "synced from the gist", force) {
+                Ok(_) => {}
+                Err(ReconcileError::Unreadable(e)) => {
+                    util::exit_with_error(format!("Could not read the gist, {}.", e))
+                }
+                Err(ReconcileError::Closed(e)) => {
+                    println!(
+                        "The gist wasn't taken over: it has other hours, but {}. Use --force to \
+                         take the gist's hours.",
+                        e
+                    );
+                    return;
+                }
+            }
+            log.save_projection();
         } else {
             println!(
                 "Updating gist from local file. New content:\n{}",
@@ -72,39 +623,39 @@ fn should_update_local_file_from_gist(gist_last_updated: DateTime<FixedOffset>)
     }
 }
 
-struct Cancel;
-
-fn write_backups_in_background(start: Instant) -> (thread::JoinHandle<()>, Sender<Cancel>) {
-    let (sender, receiver) = mpsc::channel();
-    let handle = thread::spawn(move || {
-        let today = Local::today();
-        while receiver.try_recv().is_err() {
-            thread::sleep(time::Duration::from_secs(3));
-            let mut timesheet = load_timesheet();
-            timesheet.add_hours(&today, &Duration::from_std(start.elapsed()).unwrap());
-            timesheet.generate_report().save_backup();
-        }
-    });
-    (handle, sender)
-}
-
-fn load_timesheet() -> Timesheet {
-    let report = Report::load();
-    Timesheet::parse_report(&report)
-}
-
 fn show_remaining_work() {
-    let timesheet = load_timesheet();
+    let timesheet = Timesheet::load();
     if let Some(work) = timesheet.remaining_work() {
-        println!(
-            "Including today, {} days remaining this month, which is about {} work per day.",
-            work.num_working_days(IncludeToday::Yes),
-            util::format_duration(work.time_per_day(IncludeToday::Yes))
-        );
-        println!(
-            "Not including today, {} days remaining this month, which is about {} work per day.",
-            work.num_working_days(IncludeToday::No),
-            util::format_duration(work.time_per_day(IncludeToday::No))
-        );
+        for (include_today, label) in [
+            (IncludeToday::Yes, "Including today"),
+            (IncludeToday::No, "Not including today"),
+        ] {
+            match work.time_per_day(include_today) {
+                Some(time) => println!(
+                    "{}, {} days remaining this month, which is about {} work per day.",
+                    label,
+                    work.num_working_days(include_today),
+                    util::format_duration(time)
+                ),
+                None => println!("{}, no working days left this month.", label),
+            }
+        }
+    }
+    let today = clock::today();
+    if let Some(projection) = Stats::new(&timesheet, today).month_end_projection() {
+        let month = Period::month(today.year(), today.month());
+        println!("{}", stats::projection_line(&month, projection));
+    }
+    let settings = Settings::try_load();
+    if let Some(target) = settings.as_ref().and_then(Settings::weekly_target) {
+        let done = Period::week(today).hours(&timesheet);
+        let mode = settings
+            .as_ref()
+            .map_or(LeaveMode::default(), |s| s.leave.mode);
+        let work = WeeklyWork::new(today, done, target).with_leave(&timesheet, mode);
+        println!("{}", work.summary());
+    }
+    if let Some(overtime) = settings.and_then(|settings| settings.overtime) {
+        println!("{}", overtime.balance(&timesheet, today).summary());
     }
 }
@@ -0,0 +1,157 @@
+use chrono::{Datelike, Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::period::Period;
+use crate::timesheet::Timesheet;
+use crate::util;
+
+/// The flexitime account, from the settings.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct OvertimeSettings {
+    /// The day the balance starts at zero.
+    pub since: NaiveDate,
+    /// Hours of overtime carried into the next month at most, the rest expires.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_overtime: Option<f64>,
+    /// Hours of undertime carried into the next month at most, the rest is dropped.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_undertime: Option<f64>,
+}
+
+/// The hours worked more (or, if negative, less) than the targets since the start.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Balance {
+    pub since: NaiveDate,
+    pub until: NaiveDate,
+    pub balance: Duration,
+    /// Overtime above the cap that expired at the end of a month.
+    pub expired: Duration,
+    /// Undertime beyond the cap that was dropped at the end of a month.
+    pub dropped: Duration,
+}
+
+impl OvertimeSettings {
+    /// The balance after `until`. Leave counts as worked, except for comp days. Months are capped
+    /// when they end, the month of `until` isn't unless it ends that day.
+    pub fn balance(&self, timesheet: &Timesheet, until: NaiveDate) -> Balance {
+        let since = self.since;
+        let hours = |h: Option<f64>| h.map(|h| Duration::seconds((h * 3600.0).round() as i64));
+        let (max_overtime, max_undertime) = (hours(self.max_overtime), hours(self.max_undertime));
+        let mut balance = Balance {
+            since,
+            until,
+            balance: Duration::zero(),
+            expired: Duration::zero(),
+            dropped: Duration::zero(),
+        };
+        let mut month = Period::month(since.year(), since.month());
+        while month.from <= until {
+            let part = Period::range(month.from.max(since), month.to.min(until));
+            balance.balance =
+                balance.balance + part.hours(timesheet) + part.leave_until(timesheet, part.to)
+                    - part.target_until(part.to);
+            if month.to <= until {
+                if let Some(max) = max_overtime.filter(|max| balance.balance > *max) {
+                    balance.expired = balance.expired + balance.balance - max;
+                    balance.balance = max;
+                }
+                if let Some(max) = max_undertime.filter(|max| balance.balance < -*max) {
+                    balance.dropped = balance.dropped - balance.balance - max;
+                    balance.balance = -max;
+                }
+            }
+            let next = month.to.succ();
+            month = Period::month(next.year(), next.month());
+        }
+        balance
+    }
+}
+
+impl Balance {
+    /// Like `overtime +12:30:00`.
+    pub fn short(&self) -> String {
+        format!("overtime {}", signed(self.balance))
+    }
+
+    /// Like `Overtime balance +12:30:00 since 01.01.2026 (02:00:00 expired)`.
+    pub fn summary(&self) -> String {
+        let mut line = format!(
+            "Overtime balance {} since {}",
+            signed(self.balance),
+            self.since.format("%d.%m.%Y")
+        );
+        let capped: Vec<_> = [(self.expired, "expired"), (self.dropped, "dropped")]
+            .iter()
+            .filter(|(duration, _)| *duration > Duration::zero())
+            .map(|(duration, what)| format!("{} {}", util::format_duration(*duration), what))
+            .collect();
+        if !capped.is_empty() {
+            line.push_str(&format!(" ({})", capped.join(", ")));
+        }
+        line
+    }
+}
+
+fn signed(duration: Duration) -> String {
+    if duration < Duration::zero() {
+        format!("-{}", util::format_duration(-duration))
+    } else {
+        format!("+{}", util::format_duration(duration))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::report::Report;
+
+    use super::*;
+
+    #[test]
+    fn test_balance() {
+        // 22 working days in September 2026 and in October.
+        let timesheet = Timesheet::parse_report(&Report(
+            "
+01.09.2026 170:00:00
+01.10.2026 140:00:00
+"
+            .to_owned(),
+        ));
+        let mut settings = OvertimeSettings {
+            since: NaiveDate::from_ymd(2026, 9, 1),
+            max_overtime: None,
+            max_undertime: None,
+        };
+
+        assert_eq!(
+            settings
+                .balance(&timesheet, NaiveDate::from_ymd(2026, 9, 30))
+                .balance,
+            Duration::hours(10)
+        );
+        assert_eq!(
+            settings
+                .balance(&timesheet, NaiveDate::from_ymd(2026, 10, 31))
+                .balance,
+            Duration::hours(-10)
+        );
+
+        settings.max_overtime = Some(5.0);
+        let balance = settings.balance(&timesheet, NaiveDate::from_ymd(2026, 10, 31));
+        assert_eq!(balance.balance, Duration::hours(-15));
+        assert_eq!(balance.expired, Duration::hours(5));
+
+        settings.max_undertime = Some(12.5);
+        let balance = settings.balance(&timesheet, NaiveDate::from_ymd(2026, 10, 31));
+        assert_eq!(balance.balance, Duration::minutes(-12 * 60 - 30));
+        assert_eq!(
+            balance.summary(),
+            "Overtime balance -12:30:00 since 01.09.2026 (05:00:00 expired, 02:30:00 dropped)"
+        );
+
+        // Starting mid-month only counts the target from then on.
+        settings.since = NaiveDate::from_ymd(2026, 9, 16);
+        settings.max_undertime = None;
+        let balance = settings.balance(&timesheet, NaiveDate::from_ymd(2026, 9, 30));
+        assert_eq!(balance.balance, -Duration::hours(160) * 11 / 22);
+    }
+}
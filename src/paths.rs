@@ -0,0 +1,97 @@
+use std::env;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::settings::Settings;
+use crate::util;
+
+/// Environment variable pointing to the settings file, overridden by `--config`.
+pub const CONFIG_ENV_VAR: &str = "HOURS_CONFIG";
+
+static CONFIG_FILE: OnceLock<PathBuf> = OnceLock::new();
+static REPORT_FILE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Uses `path` as the settings file instead of the default one. Has to be called before any
+/// other function in this module.
+pub fn set_config_file(path: PathBuf) {
+    CONFIG_FILE.set(path).unwrap();
+}
+
+pub fn config_file() -> PathBuf {
+    CONFIG_FILE
+        .get_or_init(|| match env::var_os(CONFIG_ENV_VAR) {
+            Some(path) => PathBuf::from(path),
+            None => config_home().join("hours.yaml"),
+        })
+        .clone()
+}
+
+/// The file the hours are kept in. It's `data_file` from the settings if set, otherwise
+/// `~/hours.txt` if it exists from before data files were configurable, otherwise
+/// `$XDG_DATA_HOME/hours/hours.txt`.
+pub fn report_file() -> PathBuf {
+    REPORT_FILE
+        .get_or_init(|| {
+            Settings::try_load()
+                .and_then(|settings| settings.data_file)
+                .map(|path| {
+                    let path = util::expand_home(&path);
+                    config_file().parent().unwrap().join(path)
+                })
+                .unwrap_or_else(default_report_file)
+        })
+        .clone()
+}
+
+pub fn backup_report_file() -> PathBuf {
+    report_file().with_extension("bak.txt")
+}
+
+/// Held by the process tracking time on the report file.
+pub fn lock_file() -> PathBuf {
+    report_file().with_extension("lock")
+}
+
+/// The running session's state, see `session::SessionState`.
+pub fn session_file() -> PathBuf {
+    report_file().with_extension("session.json")
+}
+
+/// Everything that happened to the timesheet, see `events::EventLog`.
+pub fn event_log_file() -> PathBuf {
+    report_file().with_extension("events.jsonl")
+}
+
+/// The daemon's control socket.
+pub fn socket_file() -> PathBuf {
+    report_file().with_extension("sock")
+}
+
+/// Where the daemon's output goes once it's in the background.
+pub fn daemon_log_file() -> PathBuf {
+    report_file().with_extension("daemon.log")
+}
+
+fn default_report_file() -> PathBuf {
+    let legacy = util::home_dir().join("hours.txt");
+    if legacy.exists() {
+        legacy
+    } else {
+        data_home().join("hours").join("hours.txt")
+    }
+}
+
+fn config_home() -> PathBuf {
+    xdg_dir("XDG_CONFIG_HOME").unwrap_or_else(|| util::home_dir().join(".config"))
+}
+
+fn data_home() -> PathBuf {
+    xdg_dir("XDG_DATA_HOME").unwrap_or_else(|| util::home_dir().join(".local/share"))
+}
+
+/// Reads an XDG base directory variable, which the spec says to ignore if it's not absolute.
+fn xdg_dir(var: &str) -> Option<PathBuf> {
+    env::var_os(var)
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+}
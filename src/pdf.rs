@@ -0,0 +1,369 @@
+use chrono::{Datelike, Duration};
+use serde::{Deserialize, Serialize};
+
+use crate::period::Period;
+use crate::report::ReportFormat;
+use crate::timesheet::Timesheet;
+
+/// A4 in points.
+const PAGE_WIDTH: f64 = 595.0;
+const PAGE_HEIGHT: f64 = 842.0;
+const MARGIN: f64 = 56.0;
+const ROW_HEIGHT: f64 = 18.0;
+
+/// The widths of Helvetica's printable ASCII characters, in thousandths of the font size.
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, 1015, 667, 667, 722, 722, 667,
+    611, 778, 722, 278, 500, 667, 556, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 278, 278, 278, 469, 556, 333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500,
+    222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+
+/// What the PDF timesheet's header says, from the settings.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PdfSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub company: Option<String>,
+    /// The client's address, one line per line.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_address: Option<String>,
+    /// Whether to add lines for the contractor's and the client's signatures.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub signature_lines: bool,
+}
+
+impl PdfSettings {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Font {
+    Regular,
+    Bold,
+}
+
+/// A PDF with Helvetica text and lines, written without any dependencies.
+#[derive(Default)]
+struct Document {
+    pages: Vec<Vec<u8>>,
+}
+
+impl Document {
+    fn new_page(&mut self) {
+        self.pages.push(Vec::new());
+    }
+
+    fn content(&mut self) -> &mut Vec<u8> {
+        self.pages.last_mut().unwrap()
+    }
+
+    fn text(&mut self, x: f64, y: f64, size: f64, font: Font, text: &str) {
+        let font = match font {
+            Font::Regular => "F1",
+            Font::Bold => "F2",
+        };
+        let content = self.content();
+        content.extend(format!("BT /{} {} Tf {:.2} {:.2} Td (", font, size, x, y).bytes());
+        content.extend(encode(text));
+        content.extend(b") Tj ET\n");
+    }
+
+    /// Text ending at `x`. Bold text is measured as regular text, which is a little narrower.
+    fn text_right(&mut self, x: f64, y: f64, size: f64, font: Font, text: &str) {
+        self.text(x - text_width(text, size), y, size, font, text);
+    }
+
+    fn line(&mut self, x1: f64, y1: f64, x2: f64, y2: f64) {
+        let line = format!("{:.2} {:.2} m {:.2} {:.2} l S\n", x1, y1, x2, y2);
+        self.content().extend(line.bytes());
+    }
+
+    fn to_bytes(&self, title: &str) -> Vec<u8> {
+        // Objects 1 to 5 are the catalog, the page tree, the info and the two fonts, then each
+        // page is followed by its content stream.
+        let kids: Vec<_> = (0..self.pages.len())
+            .map(|i| format!("{} 0 R", 6 + i * 2))
+            .collect();
+        let mut objects: Vec<Vec<u8>> = vec![
+            b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+            format!(
+                "<< /Type /Pages /Kids [{}] /Count {} >>",
+                kids.join(" "),
+                self.pages.len()
+            )
+            .into_bytes(),
+            [
+                b"<< /Title (".as_ref(),
+                &encode(title),
+                b") /Producer (hours) >>",
+            ]
+            .concat(),
+            font_object("Helvetica"),
+            font_object("Helvetica-Bold"),
+        ];
+        for (i, content) in self.pages.iter().enumerate() {
+            objects.push(
+                format!(
+                    "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+                     /Resources << /Font << /F1 4 0 R /F2 5 0 R >> >> /Contents {} 0 R >>",
+                    PAGE_WIDTH,
+                    PAGE_HEIGHT,
+                    7 + i * 2
+                )
+                .into_bytes(),
+            );
+            objects.push(
+                [
+                    format!("<< /Length {} >>\nstream\n", content.len()).as_bytes(),
+                    content,
+                    b"endstream",
+                ]
+                .concat(),
+            );
+        }
+
+        let mut pdf = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+        let mut offsets = Vec::new();
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            pdf.extend(format!("{} 0 obj\n", i + 1).bytes());
+            pdf.extend(object);
+            pdf.extend(b"\nendobj\n");
+        }
+        let xref = pdf.len();
+        pdf.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).bytes());
+        for offset in offsets {
+            pdf.extend(format!("{:010} 00000 n \n", offset).bytes());
+        }
+        pdf.extend(
+            format!(
+                "trailer\n<< /Size {} /Root 1 0 R /Info 3 0 R >>\nstartxref\n{}\n%%EOF\n",
+                objects.len() + 1,
+                xref
+            )
+            .bytes(),
+        );
+        pdf
+    }
+}
+
+fn font_object(name: &str) -> Vec<u8> {
+    format!(
+        "<< /Type /Font /Subtype /Type1 /BaseFont /{} /Encoding /WinAnsiEncoding >>",
+        name
+    )
+    .into_bytes()
+}
+
+/// `text` in WinAnsiEncoding, escaped for a PDF string. Characters it lacks become `?`.
+fn encode(text: &str) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => bytes.extend([b'\\', c as u8]),
+            ' '..='~' | '\u{a0}'..='\u{ff}' => bytes.push(c as u32 as u8),
+            '€' => bytes.push(0x80),
+            '‘' => bytes.push(0x91),
+            '’' => bytes.push(0x92),
+            '“' => bytes.push(0x93),
+            '”' => bytes.push(0x94),
+            '–' => bytes.push(0x96),
+            '—' => bytes.push(0x97),
+            _ => bytes.push(b'?'),
+        }
+    }
+    bytes
+}
+
+/// The width of `text` in regular Helvetica at `size`.
+fn text_width(text: &str, size: f64) -> f64 {
+    let thousandths: u32 = text
+        .chars()
+        .map(|c| match c {
+            ' '..='~' => u32::from(HELVETICA_WIDTHS[c as usize - 32]),
+            _ => 556,
+        })
+        .sum();
+    f64::from(thousandths) * size / 1000.0
+}
+
+/// The timesheet of `month` as a PDF, with the header from `settings`, a row per day with hours,
+/// the month's total and, if configured, lines to sign.
+pub fn timesheet(
+    timesheet: &Timesheet,
+    month: &Period,
+    settings: &PdfSettings,
+    format: &ReportFormat,
+) -> Vec<u8> {
+    let mut document = Document::default();
+    document.new_page();
+    let right = PAGE_WIDTH - MARGIN;
+    let mut y = PAGE_HEIGHT - MARGIN;
+
+    let mut from = Vec::new();
+    if let Some(name) = &settings.name {
+        from.push((Font::Bold, name.as_str()));
+    }
+    if let Some(company) = &settings.company {
+        from.push((Font::Regular, company.as_str()));
+    }
+    for (i, (font, line)) in from.iter().enumerate() {
+        document.text(MARGIN, y - i as f64 * 14.0, 11.0, *font, line);
+    }
+    let address: Vec<_> = settings
+        .client_address
+        .iter()
+        .flat_map(|address| address.lines().map(str::trim))
+        .collect();
+    for (i, line) in address.iter().enumerate() {
+        document.text(
+            MARGIN + 300.0,
+            y - i as f64 * 14.0,
+            11.0,
+            Font::Regular,
+            line,
+        );
+    }
+    y -= from.len().max(address.len()) as f64 * 14.0 + 36.0;
+
+    let title = format!(
+        "Timesheet {} {}",
+        format.month_name(month.from.month()),
+        month.from.year()
+    );
+    document.text(MARGIN, y, 18.0, Font::Bold, &title);
+    y -= 36.0;
+
+    let table_header = |document: &mut Document, y: f64| {
+        document.text(MARGIN, y, 11.0, Font::Bold, "Date");
+        document.text_right(right, y, 11.0, Font::Bold, "Hours");
+        document.line(MARGIN, y - 6.0, right, y - 6.0);
+    };
+    table_header(&mut document, y);
+    y -= ROW_HEIGHT + 4.0;
+    let entries: Vec<_> = timesheet
+        .entries()
+        .filter(|(date, hours)| month.contains(*date) && *hours != Duration::zero())
+        .collect();
+    for (date, hours) in &entries {
+        if y < MARGIN + ROW_HEIGHT {
+            document.new_page();
+            y = PAGE_HEIGHT - MARGIN;
+            table_header(&mut document, y);
+            y -= ROW_HEIGHT + 4.0;
+        }
+        let day = format!("{} {}", date.format("%a"), format.format_date(date));
+        document.text(MARGIN, y, 11.0, Font::Regular, &day);
+        document.text_right(
+            right,
+            y,
+            11.0,
+            Font::Regular,
+            &format.format_duration(*hours),
+        );
+        y -= ROW_HEIGHT;
+    }
+
+    // The total and the signatures are kept together.
+    let needed = if settings.signature_lines {
+        120.0
+    } else {
+        30.0
+    };
+    if y < MARGIN + needed {
+        document.new_page();
+        y = PAGE_HEIGHT - MARGIN;
+    }
+    document.line(MARGIN, y + ROW_HEIGHT - 6.0, right, y + ROW_HEIGHT - 6.0);
+    let total = month.hours(timesheet);
+    document.text(MARGIN, y - 4.0, 11.0, Font::Bold, "Total");
+    document.text_right(
+        right,
+        y - 4.0,
+        11.0,
+        Font::Bold,
+        &format.format_duration(total),
+    );
+
+    if settings.signature_lines {
+        y -= 90.0;
+        let width = (right - MARGIN - 40.0) / 2.0;
+        let client = MARGIN + width + 40.0;
+        document.line(MARGIN, y, MARGIN + width, y);
+        document.line(client, y, right, y);
+        let signer = settings.name.as_deref().unwrap_or("Contractor");
+        document.text(
+            MARGIN,
+            y - 14.0,
+            9.0,
+            Font::Regular,
+            &format!("Date, {}", signer),
+        );
+        document.text(client, y - 14.0, 9.0, Font::Regular, "Date, client");
+    }
+    document.to_bytes(&title)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::report::Report;
+
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode("a(b)\\"), b"a\\(b\\)\\\\");
+        assert_eq!(encode("Müller €"), b"M\xfcller \x80");
+        assert_eq!(encode("日"), b"?");
+        assert!((text_width("10:00", 10.0) - 25.02).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_timesheet() {
+        let timesheet = Timesheet::parse_report(&Report(
+            "
+31.08.2026 08:00:00
+01.09.2026 08:00:00
+02.09.2026 06:30:00
+"
+            .to_owned(),
+        ));
+        let settings = PdfSettings {
+            name: Some("Jane Doe".to_owned()),
+            company: None,
+            client_address: Some("Acme Corp\nMain Street 1".to_owned()),
+            signature_lines: true,
+        };
+
+        let pdf = super::timesheet(
+            &timesheet,
+            &Period::month(2026, 9),
+            &settings,
+            &ReportFormat::default(),
+        );
+
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.starts_with("%PDF-1.4\n"));
+        assert!(text.ends_with("%%EOF\n"));
+        assert!(text.contains("(Timesheet September 2026) Tj"));
+        assert!(text.contains("(Tue 01.09.2026) Tj"));
+        assert!(!text.contains("31.08.2026"));
+        assert!(text.contains("(14:30:00) Tj"));
+        assert!(text.contains("(Date, Jane Doe) Tj"));
+        // The cross-reference table points at the objects.
+        let xref: usize = text.lines().rev().nth(1).unwrap().parse().unwrap();
+        assert!(pdf[xref..].starts_with(b"xref\n0 8\n"));
+        let first = String::from_utf8_lossy(&pdf[xref..])
+            .lines()
+            .nth(3)
+            .unwrap()[..10]
+            .to_owned();
+        assert!(pdf[first.parse::<usize>().unwrap()..].starts_with(b"1 0 obj"));
+    }
+}
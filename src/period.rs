@@ -0,0 +1,432 @@
+use chrono::{Datelike, Duration, Month, NaiveDate, Weekday};
+use num_traits::cast::FromPrimitive;
+
+use crate::leave::{self, LeaveMode, LeaveSettings, LEAVE_KINDS};
+use crate::pomodoro;
+use crate::remaining_work;
+use crate::timesheet::{self, Timesheet};
+use crate::util;
+
+/// A range of days to report on, including both ends.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Period {
+    pub name: String,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+impl Period {
+    /// The ISO week containing `date`.
+    pub fn week(date: NaiveDate) -> Self {
+        let week = date.iso_week();
+        let from = date - Duration::days(date.weekday().num_days_from_monday() as i64);
+        Self {
+            name: format!("week {} {}", week.week(), week.year()),
+            from,
+            to: from + Duration::days(6),
+        }
+    }
+
+    pub fn month(year: i32, month: u32) -> Self {
+        let (next_year, next_month) = if month == 12 {
+            (year + 1, 1)
+        } else {
+            (year, month + 1)
+        };
+        Self {
+            name: format!("{} {}", Month::from_u32(month).unwrap().name(), year),
+            from: NaiveDate::from_ymd(year, month, 1),
+            to: NaiveDate::from_ymd(next_year, next_month, 1).pred(),
+        }
+    }
+
+    pub fn quarter(year: i32, quarter: u32) -> Self {
+        let first_month = (quarter - 1) * 3 + 1;
+        Self {
+            name: format!("Q{} {}", quarter, year),
+            from: Self::month(year, first_month).from,
+            to: Self::month(year, first_month + 2).to,
+        }
+    }
+
+    pub fn year(year: i32) -> Self {
+        Self {
+            name: year.to_string(),
+            from: NaiveDate::from_ymd(year, 1, 1),
+            to: NaiveDate::from_ymd(year, 12, 31),
+        }
+    }
+
+    pub fn range(from: NaiveDate, to: NaiveDate) -> Self {
+        Self {
+            name: format!("{} to {}", format_date(&from), format_date(&to)),
+            from,
+            to,
+        }
+    }
+
+    /// Parses the value of `--week` (`2026-W38`), `--month` (`2026-09`), `--quarter` (`2026-Q3`)
+    /// or `--year` (`2026`). Without a value, it's the period containing `today`.
+    pub fn parse(flag: &str, value: Option<&str>, today: NaiveDate) -> Option<Self> {
+        match (flag, value) {
+            ("--week", None) => Some(Self::week(today)),
+            ("--week", Some(value)) => {
+                let (year, week) = value.split_once("-W")?;
+                let date = NaiveDate::from_isoywd_opt(
+                    year.parse().ok()?,
+                    week.parse().ok()?,
+                    Weekday::Mon,
+                )?;
+                Some(Self::week(date))
+            }
+            ("--month", None) => Some(Self::month(today.year(), today.month())),
+            ("--month", Some(value)) => {
+                let (year, month) = value.split_once('-')?;
+                let month = month
+                    .parse()
+                    .ok()
+                    .filter(|month| (1..=12).contains(month))?;
+                Some(Self::month(year.parse().ok()?, month))
+            }
+            ("--quarter", None) => Some(Self::quarter(today.year(), today.month0() / 3 + 1)),
+            ("--quarter", Some(value)) => {
+                let (year, quarter) = value.split_once("-Q")?;
+                let quarter = quarter.parse().ok().filter(|q| (1..=4).contains(q))?;
+                Some(Self::quarter(year.parse().ok()?, quarter))
+            }
+            ("--year", None) => Some(Self::year(today.year())),
+            ("--year", Some(value)) => Some(Self::year(value.parse().ok()?)),
+            _ => None,
+        }
+    }
+
+    /// Parses a date for `--from` and `--to`, like `2026-09-01`.
+    pub fn parse_date(value: &str) -> Option<NaiveDate> {
+        let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+        Some(date)
+    }
+
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        date >= self.from && date <= self.to
+    }
+
+    /// The hours worked in the period.
+    pub fn hours(&self, timesheet: &Timesheet) -> Duration {
+        timesheet
+            .entries()
+            .filter(|(date, _)| self.contains(*date))
+            .fold(Duration::zero(), |acc, (_, duration)| acc + duration)
+    }
+
+    pub fn days(&self) -> impl Iterator<Item = NaiveDate> {
+        let to = self.to;
+        std::iter::successors(Some(self.from), move |date| {
+            Some(date.succ()).filter(|date| *date <= to)
+        })
+    }
+
+    /// Working days in the period up to and including `until`.
+    pub fn working_days_until(&self, until: NaiveDate) -> i32 {
+        self.days()
+            .filter(|date| *date <= until && remaining_work::is_working_day(date.weekday()))
+            .count() as i32
+    }
+
+    /// The monthly target, spread evenly over the working days of each month.
+    pub fn target_until(&self, until: NaiveDate) -> Duration {
+        let mut target = Duration::zero();
+        let mut month = Self::month(self.from.year(), self.from.month());
+        while month.from <= self.to {
+            let part = Self::range(month.from.max(self.from), month.to.min(self.to));
+            target = target
+                + timesheet::monthly_target() * part.working_days_until(until)
+                    / month.working_days_until(month.to);
+            let next = month.to.succ();
+            month = Self::month(next.year(), next.month());
+        }
+        target
+    }
+
+    /// The share of the target covered by leave up to and including `until`.
+    pub fn leave_until(&self, timesheet: &Timesheet, until: NaiveDate) -> Duration {
+        timesheet
+            .leave()
+            .filter(|&(date, kind)| {
+                self.contains(date)
+                    && date <= until
+                    && kind.counts()
+                    && remaining_work::is_working_day(date.weekday())
+            })
+            .fold(Duration::zero(), |acc, (date, _)| {
+                acc + leave::daily_target(date)
+            })
+    }
+
+    /// A report with the hours and leave of every day, subtotals per week, the average per working
+    /// day and how that compares to the target. Working days after `today` don't count yet.
+    pub fn report(&self, timesheet: &Timesheet, today: NaiveDate, leave: &LeaveSettings) -> String {
+        let dates: Vec<_> = timesheet
+            .dates()
+            .into_iter()
+            .filter(|date| self.contains(*date))
+            .collect();
+        let spans_weeks = self.from.iso_week() != self.to.iso_week();
+        let mut lines = vec![format!("Hours for {}", self.name), String::new()];
+        let mut total = Duration::zero();
+        let mut week_total = Duration::zero();
+        for (i, &date) in dates.iter().enumerate() {
+            let duration = timesheet.get_hours(&date);
+            let kind = timesheet.get_leave(date);
+            total = total + duration;
+            week_total = week_total + duration;
+            let mut line = format!("{} {}", date.weekday(), format_date(&date));
+            if duration != Duration::zero() || kind.is_none() {
+                line.push(' ');
+                line.push_str(&util::format_duration(duration));
+            }
+            if let Some(kind) = kind {
+                line.push(' ');
+                line.push_str(kind.name());
+            }
+            let pomodoros = timesheet.get_pomodoros(date);
+            if pomodoros > 0 {
+                line.push_str(", ");
+                line.push_str(&pomodoro::count(pomodoros));
+            }
+            lines.push(line);
+            let week_ends = dates
+                .get(i + 1)
+                .is_none_or(|next| next.iso_week() != date.iso_week());
+            if spans_weeks && week_ends {
+                lines.push(format!(
+                    "Total for week {} {}\n",
+                    date.iso_week().week(),
+                    util::format_duration(week_total)
+                ));
+                week_total = Duration::zero();
+            }
+        }
+        if dates.is_empty() {
+            lines.push("No hours recorded.".to_owned());
+        }
+        if !spans_weeks || dates.is_empty() {
+            lines.push(String::new());
+        }
+
+        lines.push(format!("Total {}", util::format_duration(total)));
+        let working_days = self.working_days_until(today);
+        if working_days > 0 {
+            lines.push(format!(
+                "Average per working day {} ({} working day{})",
+                util::format_duration(total / working_days),
+                working_days,
+                if working_days == 1 { "" } else { "s" }
+            ));
+        }
+        let mut target = self.target_until(self.to);
+        let mut target_so_far = self.target_until(today);
+        let (mut counted, mut counted_so_far) = (total, total);
+        let days_off: Vec<_> = timesheet
+            .leave()
+            .filter(|(date, _)| self.contains(*date))
+            .collect();
+        if !days_off.is_empty() {
+            let (leave_hours, leave_so_far) = (
+                self.leave_until(timesheet, self.to),
+                self.leave_until(timesheet, today),
+            );
+            let effect = match leave.mode {
+                LeaveMode::ReduceTarget => {
+                    target = target - leave_hours;
+                    target_so_far = target_so_far - leave_so_far;
+                    format!("target reduced by {}", util::format_duration(leave_hours))
+                }
+                LeaveMode::Credit => {
+                    counted = counted + leave_hours;
+                    counted_so_far = counted_so_far + leave_so_far;
+                    format!("{} credited", util::format_duration(leave_hours))
+                }
+            };
+            let kinds: Vec<_> = LEAVE_KINDS
+                .iter()
+                .map(|kind| (kind, days_off.iter().filter(|(_, k)| k == kind).count()))
+                .filter(|(_, count)| *count > 0)
+                .map(|(kind, count)| format!("{} {}", count, kind.name()))
+                .collect();
+            lines.push(format!(
+                "Leave {} day{} ({}), {}",
+                days_off.len(),
+                if days_off.len() == 1 { "" } else { "s" },
+                kinds.join(", "),
+                effect
+            ));
+        }
+        if target_so_far < target {
+            lines.push(format!(
+                "Target {}, so far {}, {}",
+                util::format_duration(target),
+                util::format_duration(target_so_far),
+                ahead_or_behind(counted_so_far - target_so_far)
+            ));
+        } else {
+            lines.push(format!(
+                "Target {}, {}",
+                util::format_duration(target),
+                ahead_or_behind(counted - target)
+            ));
+        }
+        for closing in timesheet.closings() {
+            if self.contains(closing.period().from) || self.contains(closing.period().to) {
+                lines.push(if timesheet.is_intact(closing) {
+                    format!("{} is closed.", closing.period().name)
+                } else {
+                    format!(
+                        "{} is closed, but its hours have changed since.",
+                        closing.period().name
+                    )
+                });
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+fn ahead_or_behind(difference: Duration) -> String {
+    if difference < Duration::zero() {
+        format!("{} behind", util::format_duration(-difference))
+    } else {
+        format!("{} ahead", util::format_duration(difference))
+    }
+}
+
+fn format_date(d: &NaiveDate) -> String {
+    d.format("%d.%m.%Y").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::report::Report;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_period() {
+        let today = NaiveDate::from_ymd(2026, 9, 16);
+        let cases = [
+            (
+                "--week",
+                None,
+                Some((
+                    NaiveDate::from_ymd(2026, 9, 14),
+                    NaiveDate::from_ymd(2026, 9, 20),
+                )),
+            ),
+            (
+                "--week",
+                Some("2026-W01"),
+                Some((
+                    NaiveDate::from_ymd(2025, 12, 29),
+                    NaiveDate::from_ymd(2026, 1, 4),
+                )),
+            ),
+            (
+                "--month",
+                Some("2026-02"),
+                Some((
+                    NaiveDate::from_ymd(2026, 2, 1),
+                    NaiveDate::from_ymd(2026, 2, 28),
+                )),
+            ),
+            ("--month", Some("2026-13"), None),
+            (
+                "--quarter",
+                None,
+                Some((
+                    NaiveDate::from_ymd(2026, 7, 1),
+                    NaiveDate::from_ymd(2026, 9, 30),
+                )),
+            ),
+            (
+                "--quarter",
+                Some("2026-Q4"),
+                Some((
+                    NaiveDate::from_ymd(2026, 10, 1),
+                    NaiveDate::from_ymd(2026, 12, 31),
+                )),
+            ),
+            (
+                "--year",
+                Some("2025"),
+                Some((
+                    NaiveDate::from_ymd(2025, 1, 1),
+                    NaiveDate::from_ymd(2025, 12, 31),
+                )),
+            ),
+            ("--year", Some("last"), None),
+        ];
+
+        for (flag, value, expected) in cases.iter() {
+            let period = Period::parse(flag, *value, today);
+            assert_eq!(period.map(|p| (p.from, p.to)), *expected);
+        }
+    }
+
+    #[test]
+    fn test_period_report() {
+        let timesheet = Timesheet::parse_report(&Report(
+            "
+31.08.2026 08:00:00
+01.09.2026 08:00:00
+02.09.2026 07:00:00
+07.09.2026 09:30:00
+"
+            .to_owned(),
+        ));
+        // September 2026 has 22 working days, the 7th is the 5th of them.
+        let period = Period::month(2026, 9);
+
+        let report = period.report(
+            &timesheet,
+            NaiveDate::from_ymd(2026, 9, 7),
+            &LeaveSettings::default(),
+        );
+
+        assert_eq!(
+            report,
+            "Hours for September 2026
+
+Tue 01.09.2026 08:00:00
+Wed 02.09.2026 07:00:00
+Total for week 36 15:00:00
+
+Mon 07.09.2026 09:30:00
+Total for week 37 09:30:00
+
+Total 24:30:00
+Average per working day 04:54:00 (5 working days)
+Target 160:00:00, so far 36:21:49, 11:51:49 behind"
+        );
+
+        let timesheet = Timesheet::parse_report(&Report(
+            "
+01.09.2026 08:00:00
+02.09.2026 vacation
+03.09.2026 sick
+04.09.2026 comp
+"
+            .to_owned(),
+        ));
+        let mut leave = LeaveSettings::default();
+        let report = period.report(&timesheet, NaiveDate::from_ymd(2026, 9, 4), &leave);
+        assert!(report.ends_with(
+            "Leave 3 days (1 vacation, 1 sick, 1 comp), target reduced by 14:32:43
+Target 145:27:16, so far 14:32:43, 06:32:43 behind"
+        ));
+        leave.mode = LeaveMode::Credit;
+        let report = period.report(&timesheet, NaiveDate::from_ymd(2026, 9, 4), &leave);
+        assert!(report.ends_with(
+            "Leave 3 days (1 vacation, 1 sick, 1 comp), 14:32:43 credited
+Target 160:00:00, so far 29:05:27, 06:32:43 behind"
+        ));
+    }
+}
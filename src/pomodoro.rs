@@ -0,0 +1,187 @@
+use chrono::{DateTime, Duration, FixedOffset};
+use serde::{Deserialize, Serialize};
+
+use crate::util;
+
+/// The lengths of pomodoros and their breaks in minutes, from the settings.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PomodoroSettings {
+    pub work: u32,
+    pub short_break: u32,
+    pub long_break: u32,
+    /// Every this many pomodoros, the break is a long one.
+    pub long_break_every: u32,
+}
+
+impl Default for PomodoroSettings {
+    fn default() -> Self {
+        Self {
+            work: 25,
+            short_break: 5,
+            long_break: 15,
+            long_break_every: 4,
+        }
+    }
+}
+
+impl PomodoroSettings {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// At least a minute, since with 0 the phase would change on every tick.
+    fn length(&self, phase: Phase) -> Duration {
+        let minutes = match phase {
+            Phase::Work => self.work,
+            Phase::ShortBreak => self.short_break,
+            Phase::LongBreak => self.long_break,
+        };
+        Duration::minutes(minutes.max(1) as i64)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Phase {
+    Work,
+    ShortBreak,
+    LongBreak,
+}
+
+impl Phase {
+    pub fn is_break(&self) -> bool {
+        *self != Self::Work
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Work => "work",
+            Self::ShortBreak => "short break",
+            Self::LongBreak => "long break",
+        }
+    }
+}
+
+/// The timer of the Pomodoro mode, going from work to a break and back.
+pub struct Pomodoro {
+    settings: PomodoroSettings,
+    pub phase: Phase,
+    ends_at: DateTime<FixedOffset>,
+    /// Pomodoros completed since the start.
+    pub completed: u32,
+}
+
+impl Pomodoro {
+    pub fn start(settings: PomodoroSettings, now: DateTime<FixedOffset>) -> Self {
+        Self {
+            settings,
+            phase: Phase::Work,
+            ends_at: now + settings.length(Phase::Work),
+            completed: 0,
+        }
+    }
+
+    /// Moves on to the next phase if the current one is over, and returns it.
+    pub fn advance(&mut self, now: DateTime<FixedOffset>) -> Option<Phase> {
+        if now < self.ends_at {
+            return None;
+        }
+        self.phase = match self.phase {
+            Phase::Work => {
+                self.completed += 1;
+                let every = self.settings.long_break_every;
+                if every > 0 && self.completed % every == 0 {
+                    Phase::LongBreak
+                } else {
+                    Phase::ShortBreak
+                }
+            }
+            Phase::ShortBreak | Phase::LongBreak => Phase::Work,
+        };
+        // The next phase starts now, not when this one was due, e.g. after a suspend.
+        self.ends_at = now + self.settings.length(self.phase);
+        Some(self.phase)
+    }
+
+    /// Like `work 00:18:32 left, 3 pomodoros`.
+    pub fn status(&self, now: DateTime<FixedOffset>) -> String {
+        let left = if self.ends_at > now {
+            self.ends_at - now
+        } else {
+            Duration::zero()
+        };
+        format!(
+            "{} {} left, {}",
+            self.phase.name(),
+            util::format_duration(left),
+            count(self.completed)
+        )
+    }
+}
+
+/// Like `1 pomodoro` or `3 pomodoros`.
+pub fn count(pomodoros: u32) -> String {
+    match pomodoros {
+        1 => "1 pomodoro".to_owned(),
+        n => format!("{} pomodoros", n),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn test_pomodoro() {
+        let start = FixedOffset::east(7200).ymd(2026, 10, 19).and_hms(9, 0, 0);
+        let settings = PomodoroSettings {
+            long_break_every: 2,
+            ..PomodoroSettings::default()
+        };
+        let mut pomodoro = Pomodoro::start(settings, start);
+
+        assert_eq!(pomodoro.advance(start + Duration::minutes(24)), None);
+        assert_eq!(
+            pomodoro.status(start + Duration::seconds(90)),
+            "work 00:23:30 left, 0 pomodoros"
+        );
+        let mut now = start + Duration::minutes(25);
+        assert_eq!(pomodoro.advance(now), Some(Phase::ShortBreak));
+        now = now + Duration::minutes(5);
+        assert_eq!(pomodoro.advance(now), Some(Phase::Work));
+        // Late by a minute, the break still lasts 15 minutes.
+        now = now + Duration::minutes(26);
+        assert_eq!(pomodoro.advance(now), Some(Phase::LongBreak));
+        assert_eq!(
+            pomodoro.status(now + Duration::minutes(10)),
+            "long break 00:05:00 left, 2 pomodoros"
+        );
+        assert_eq!(
+            pomodoro.advance(now + Duration::minutes(15)),
+            Some(Phase::Work)
+        );
+        assert_eq!(pomodoro.completed, 2);
+    }
+
+    #[test]
+    fn test_pomodoro_zero_length() {
+        let start = FixedOffset::east(7200).ymd(2026, 10, 19).and_hms(9, 0, 0);
+        let settings = PomodoroSettings {
+            work: 0,
+            short_break: 0,
+            ..PomodoroSettings::default()
+        };
+        let mut pomodoro = Pomodoro::start(settings, start);
+
+        assert_eq!(pomodoro.advance(start), None);
+        let now = start + Duration::minutes(1);
+        assert_eq!(pomodoro.advance(now), Some(Phase::ShortBreak));
+        assert_eq!(pomodoro.advance(now), None);
+        assert_eq!(
+            pomodoro.status(now),
+            "short break 00:01:00 left, 1 pomodoro"
+        );
+    }
+}
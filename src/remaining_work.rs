@@ -1,8 +1,14 @@
-use chrono::{Date, Datelike, Duration, Local, Weekday};
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveDate, Weekday};
+
+use crate::events::{self, Event};
+use crate::leave::LeaveMode;
+use crate::timesheet::Timesheet;
+use crate::util;
 
 pub struct RemainingWork {
-    date: Date<Local>,
+    date: NaiveDate,
     remaining_time: Duration,
+    days_off: Vec<NaiveDate>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -12,7 +18,7 @@ pub enum IncludeToday {
 }
 
 impl RemainingWork {
-    pub fn new(date: Date<Local>, remaining_time: Duration) -> Self {
+    pub fn new(date: NaiveDate, remaining_time: Duration) -> Self {
         Self {
             date,
             remaining_time: if remaining_time < Duration::zero() {
@@ -20,9 +26,15 @@ impl RemainingWork {
             } else {
                 remaining_time
             },
+            days_off: Vec::new(),
         }
     }
 
+    /// Leaves out days taken off from the remaining working days.
+    pub fn excluding(self, days_off: Vec<NaiveDate>) -> Self {
+        Self { days_off, ..self }
+    }
+
     pub fn num_working_days(&self, include_today: IncludeToday) -> u8 {
         let month = self.date.month();
         let mut date = match include_today {
@@ -31,7 +43,84 @@ impl RemainingWork {
         };
         let mut remaining_days = 0;
         while date.month() == month {
-            if is_working_day(date.weekday()) {
+            if is_working_day(date.weekday()) && !self.days_off.contains(&date) {
+                remaining_days += 1;
+            }
+            date = date.succ();
+        }
+        remaining_days
+    }
+
+    /// `None` if there are no working days left this month.
+    pub fn time_per_day(&self, include_today: IncludeToday) -> Option<Duration> {
+        match self.num_working_days(include_today) {
+            0 => None,
+            days => Some(self.remaining_time / days as i32),
+        }
+    }
+}
+
+/// Like `RemainingWork`, but for a weekly target and the ISO week of `date`.
+pub struct WeeklyWork {
+    date: NaiveDate,
+    done: Duration,
+    target: Duration,
+    days_off: Vec<NaiveDate>,
+}
+
+impl WeeklyWork {
+    pub fn new(date: NaiveDate, done: Duration, target: Duration) -> Self {
+        Self {
+            date,
+            done,
+            target,
+            days_off: Vec::new(),
+        }
+    }
+
+    /// Leaves out the week's days taken off from the working days. Each one that counts covers a
+    /// working day's share of the target, which lowers the target or is credited, see `LeaveMode`.
+    pub fn with_leave(self, timesheet: &Timesheet, mode: LeaveMode) -> Self {
+        let monday = self.date - Duration::days(self.date.weekday().num_days_from_monday() as i64);
+        let week: Vec<_> = (0..7)
+            .map(|day| monday + Duration::days(day))
+            .filter(|date| is_working_day(date.weekday()))
+            .collect();
+        let share = self.target / week.len() as i32;
+        let mut days_off = Vec::new();
+        let mut leave = Duration::zero();
+        for date in week {
+            if let Some(kind) = timesheet.get_leave(date) {
+                days_off.push(date);
+                if kind.counts() {
+                    leave = leave + share;
+                }
+            }
+        }
+        let (done, target) = match mode {
+            LeaveMode::ReduceTarget => (self.done, self.target - leave),
+            LeaveMode::Credit => (self.done + leave, self.target),
+        };
+        Self {
+            done,
+            target,
+            days_off,
+            ..self
+        }
+    }
+
+    pub fn remaining_time(&self) -> Duration {
+        (self.target - self.done).max(Duration::zero())
+    }
+
+    pub fn num_working_days(&self, include_today: IncludeToday) -> u8 {
+        let mut date = match include_today {
+            IncludeToday::Yes => self.date,
+            IncludeToday::No => self.date.succ(),
+        };
+        let mut remaining_days = 0;
+        while date.iso_week() == self.date.iso_week() {
+            if is_working_day(date.weekday()) && !self.days_off.contains(&date) {
                 remaining_days += 1;
             }
             date = date.succ();
@@ -39,18 +128,118 @@ impl RemainingWork {
         remaining_days
     }
 
-    pub fn time_per_day(&self, include_today: IncludeToday) -> Duration {
-        self.remaining_time / self.num_working_days(include_today) as i32
+    /// `None` if there are no working days left this week.
+    pub fn time_per_day(&self, include_today: IncludeToday) -> Option<Duration> {
+        match self.num_working_days(include_today) {
+            0 => None,
+            days => Some(self.remaining_time() / days as i32),
+        }
+    }
+
+    /// Like `20:00:00 of 38:30:00 this week`.
+    pub fn progress(&self) -> String {
+        format!(
+            "{} of {} this week",
+            util::format_duration(self.done),
+            util::format_duration(self.target)
+        )
+    }
+
+    /// The progress, what's left and how to spread it over the rest of the week.
+    pub fn summary(&self) -> String {
+        if self.remaining_time() == Duration::zero() {
+            return format!("{}, the weekly target is reached.", self.progress());
+        }
+        let per_day = |include_today| match self.time_per_day(include_today) {
+            Some(time) => format!(
+                "{} per day on {}",
+                util::format_duration(time),
+                days(self.num_working_days(include_today))
+            ),
+            None => "no working days left".to_owned(),
+        };
+        format!(
+            "{}, {} to go: about {} including today, {} not including today.",
+            self.progress(),
+            util::format_duration(self.remaining_time()),
+            per_day(IncludeToday::Yes),
+            per_day(IncludeToday::No)
+        )
+    }
+}
+
+/// Today's share of the remaining work, and when it's done.
+pub struct Workday {
+    target: Duration,
+    /// When work started today.
+    started_at: DateTime<FixedOffset>,
+    /// The unpaid break to take during the day.
+    lunch_break: Duration,
+}
+
+impl Workday {
+    pub fn new(target: Duration, started_at: DateTime<FixedOffset>, lunch_break: Duration) -> Self {
+        Self {
+            target,
+            started_at,
+            lunch_break,
+        }
+    }
+
+    /// The workday of `date`, if it's a working day that isn't taken off and work on it has
+    /// started.
+    pub fn on(
+        date: NaiveDate,
+        timesheet: &Timesheet,
+        events: &[Event],
+        lunch_break: Duration,
+    ) -> Option<Self> {
+        if !is_working_day(date.weekday()) || timesheet.get_leave(date).is_some() {
+            return None;
+        }
+        let started_at = events::day_start(events, date)?;
+        let target = timesheet
+            .remaining_work_from(date)
+            .time_per_day(IncludeToday::Yes)?;
+        Some(Self::new(target, started_at, lunch_break))
+    }
+
+    pub fn remaining_time(&self, done: Duration) -> Duration {
+        (self.target - done).max(Duration::zero())
+    }
+
+    /// When `done` reaches the target, working on from `now`. Time since the start that wasn't
+    /// worked counts towards the lunch break, the rest of it is still to be taken.
+    pub fn leave_at(&self, done: Duration, now: DateTime<FixedOffset>) -> DateTime<FixedOffset> {
+        let taken = (now - self.started_at - done).max(Duration::zero());
+        let lunch_left = (self.lunch_break - taken).max(Duration::zero());
+        now + self.remaining_time(done) + lunch_left
+    }
+
+    /// Like `03:12:45 to go, leave at 17:42`.
+    pub fn countdown(&self, done: Duration, now: DateTime<FixedOffset>) -> String {
+        if self.remaining_time(done) == Duration::zero() {
+            return "done for today".to_owned();
+        }
+        format!(
+            "{} to go, leave at {}",
+            util::format_duration(self.remaining_time(done)),
+            self.leave_at(done, now).format("%H:%M")
+        )
     }
 }
 
-fn is_working_day(weekday: Weekday) -> bool {
-    match weekday {
-        Weekday::Sat | Weekday::Sun => false,
-        _ => true,
+fn days(days: u8) -> String {
+    match days {
+        1 => "1 day".to_owned(),
+        days => format!("{} days", days),
     }
 }
 
+pub fn is_working_day(weekday: Weekday) -> bool {
+    !matches!(weekday, Weekday::Sat | Weekday::Sun)
+}
+
 #[cfg(test)]
 mod tests {
     use chrono::TimeZone;
@@ -61,21 +250,21 @@ mod tests {
     fn test_remaining_work() {
         let cases = vec![
             (
-                Local.ymd(2021, 7, 17),
+                NaiveDate::from_ymd(2021, 7, 17),
                 Duration::hours(10),
                 IncludeToday::Yes,
                 10,
                 Duration::hours(1),
             ),
             (
-                Local.ymd(2021, 8, 20),
+                NaiveDate::from_ymd(2021, 8, 20),
                 Duration::hours(4),
                 IncludeToday::Yes,
                 8,
                 Duration::minutes(30),
             ),
             (
-                Local.ymd(2021, 8, 19),
+                NaiveDate::from_ymd(2021, 8, 19),
                 Duration::hours(4),
                 IncludeToday::No,
                 8,
@@ -91,7 +280,109 @@ mod tests {
             let time_per_day = remaining_work.time_per_day(include_today);
 
             assert_eq!(num_days, expected_num_days);
-            assert_eq!(time_per_day, expected_time_per_day);
+            assert_eq!(time_per_day, Some(expected_time_per_day));
         }
     }
+
+    #[test]
+    fn test_remaining_work_on_leave() {
+        // A Tuesday, with the rest of the month taken off.
+        let work = RemainingWork::new(NaiveDate::from_ymd(2026, 10, 27), Duration::hours(20))
+            .excluding(
+                (28..=30)
+                    .map(|day| NaiveDate::from_ymd(2026, 10, day))
+                    .collect(),
+            );
+
+        assert_eq!(work.num_working_days(IncludeToday::No), 0);
+        assert_eq!(work.time_per_day(IncludeToday::No), None);
+        assert_eq!(
+            work.time_per_day(IncludeToday::Yes),
+            Some(Duration::hours(20))
+        );
+    }
+
+    #[test]
+    fn test_weekly_work() {
+        // A Wednesday.
+        let work = WeeklyWork::new(
+            NaiveDate::from_ymd(2026, 9, 16),
+            Duration::hours(17),
+            Duration::minutes(38 * 60 + 30),
+        );
+
+        assert_eq!(work.num_working_days(IncludeToday::Yes), 3);
+        assert_eq!(work.num_working_days(IncludeToday::No), 2);
+        assert_eq!(
+            work.time_per_day(IncludeToday::Yes),
+            Some(Duration::minutes(7 * 60 + 10))
+        );
+        assert_eq!(
+            work.summary(),
+            "17:00:00 of 38:30:00 this week, 21:30:00 to go: about 07:10:00 per day on 3 days \
+             including today, 10:45:00 per day on 2 days not including today."
+        );
+        let weekend = WeeklyWork::new(
+            NaiveDate::from_ymd(2026, 9, 19),
+            Duration::hours(30),
+            Duration::hours(38),
+        );
+        assert_eq!(weekend.time_per_day(IncludeToday::No), None);
+        let done = WeeklyWork::new(
+            NaiveDate::from_ymd(2026, 9, 18),
+            Duration::hours(40),
+            Duration::hours(38),
+        );
+        assert_eq!(done.remaining_time(), Duration::zero());
+    }
+
+    #[test]
+    fn test_weekly_work_with_leave() {
+        let timesheet = Timesheet::parse_report(&crate::report::Report(
+            "14.09.2026 vacation\n15.09.2026 sick\n18.09.2026 comp".to_owned(),
+        ));
+        // A Wednesday, with Monday and Tuesday off and a comp day on Friday.
+        let date = NaiveDate::from_ymd(2026, 9, 16);
+        let target = Duration::minutes(38 * 60 + 30);
+
+        let reduced = WeeklyWork::new(date, Duration::hours(4), target)
+            .with_leave(&timesheet, LeaveMode::ReduceTarget);
+        let credited = WeeklyWork::new(date, Duration::hours(4), target)
+            .with_leave(&timesheet, LeaveMode::Credit);
+
+        assert_eq!(reduced.num_working_days(IncludeToday::Yes), 2);
+        assert_eq!(reduced.progress(), "04:00:00 of 23:06:00 this week");
+        assert_eq!(credited.progress(), "19:24:00 of 38:30:00 this week");
+        assert_eq!(reduced.remaining_time(), Duration::minutes(19 * 60 + 6));
+        assert_eq!(credited.remaining_time(), reduced.remaining_time());
+        assert_eq!(
+            reduced.time_per_day(IncludeToday::Yes),
+            Some(Duration::minutes(9 * 60 + 33))
+        );
+    }
+
+    #[test]
+    fn test_workday() {
+        let started_at = FixedOffset::east(7200).ymd(2026, 10, 19).and_hms(8, 0, 0);
+        let workday = Workday::new(Duration::hours(8), started_at, Duration::minutes(30));
+
+        // Worked through since 8:00, so the lunch break is still to come.
+        let now = started_at + Duration::hours(3);
+        assert_eq!(
+            workday.countdown(Duration::hours(3), now),
+            "05:00:00 to go, leave at 16:30"
+        );
+        // A 20 minute break was taken, 10 minutes of it are left.
+        let now = started_at + Duration::minutes(4 * 60 + 20);
+        assert_eq!(
+            workday.leave_at(Duration::hours(4), now),
+            started_at + Duration::minutes(8 * 60 + 30)
+        );
+        let now = started_at + Duration::hours(10);
+        assert_eq!(
+            workday.leave_at(Duration::hours(7), now),
+            now + Duration::hours(1)
+        );
+        assert_eq!(workday.countdown(Duration::hours(8), now), "done for today");
+    }
 }
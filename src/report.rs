@@ -1,9 +1,15 @@
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
 
-use crate::util;
+use crate::settings::Settings;
+use crate::timesheet::Timesheet;
+use crate::{paths, util};
+
+const HEADER_PREFIX: &str = "# hours report v";
+const VERSION: u32 = 2;
 
 pub struct Report(pub String);
 
@@ -16,22 +22,33 @@ impl Report {
     }
 
     pub fn save(&self) {
-        fs::write(report_path(), &self.0).unwrap();
-    }
-
-    pub fn save_backup(&self) {
-        fs::write(backup_report_path(), &self.0).unwrap();
+        write(&report_path(), &self.0);
     }
 
     pub fn commit_backup() {
-        if Self::should_commit_backup() {
+        if !Self::should_commit_backup() {
+            return;
+        }
+        let backup = Self(fs::read_to_string(backup_report_path()).unwrap());
+        if let Err(e) = Timesheet::try_parse_report(&backup) {
+            let invalid_path = backup_report_path().with_extension("invalid.txt");
             println!(
-                "Moving backup file \"{}\" to \"{}\".",
+                "Backup file \"{}\" is damaged ({}), moving it to \"{}\" and keeping \"{}\".",
                 backup_report_path().to_str().unwrap(),
+                e,
+                invalid_path.to_str().unwrap(),
                 report_path().to_str().unwrap()
             );
-            fs::rename(backup_report_path(), report_path()).unwrap();
+            fs::rename(backup_report_path(), invalid_path).unwrap();
+            return;
         }
+        println!(
+            "Moving backup file \"{}\" to \"{}\".",
+            backup_report_path().to_str().unwrap(),
+            report_path().to_str().unwrap()
+        );
+        fs::rename(backup_report_path(), report_path()).unwrap();
+        util::sync_dir(report_path().parent().unwrap()).unwrap();
     }
 
     fn should_commit_backup() -> bool {
@@ -51,14 +68,294 @@ impl Report {
         fs::metadata(report_path())
             .ok()
             .map(|report_meta| report_meta.modified().unwrap())
-            .map(|modified| DateTime::from(modified))
+            .map(DateTime::from)
+    }
+}
+
+/// How dates, durations and the monthly totals are written in the report. It's noted in the
+/// report's first line, so a report can be read whatever the current settings are.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ReportFormat {
+    pub dates: DateStyle,
+    pub durations: DurationStyle,
+    pub language: Language,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DateStyle {
+    /// `31.12.2026`
+    #[default]
+    Dotted,
+    /// `2026-12-31`
+    Iso,
+    /// `12/31/2026`
+    Us,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DurationStyle {
+    /// `07:15:00`
+    #[default]
+    Clock,
+    /// `7.25h`
+    Decimal,
+}
+
+/// The language of the month names in the totals.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    #[default]
+    En,
+    De,
+    Fr,
+    Es,
+}
+
+impl ReportFormat {
+    /// The format from the settings, or the default one if there are no settings.
+    pub fn configured() -> Self {
+        Settings::try_load()
+            .map(|settings| settings.report_format)
+            .unwrap_or_default()
+    }
+
+    /// The format `report` is written in. Reports without a header are from before there were
+    /// options, and use the default format. Returns `None` if the header can't be read.
+    pub fn of(report: &Report) -> Option<Self> {
+        match report
+            .0
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+        {
+            Some(line) if line.starts_with('#') => Self::parse_header(line),
+            _ => Some(Self::default()),
+        }
+    }
+
+    pub fn header(&self) -> String {
+        format!(
+            "{}{}, dates: {}, durations: {}, language: {}",
+            HEADER_PREFIX,
+            VERSION,
+            name(&self.dates),
+            name(&self.durations),
+            name(&self.language)
+        )
+    }
+
+    fn parse_header(line: &str) -> Option<Self> {
+        let mut pieces = line.strip_prefix(HEADER_PREFIX)?.split(',');
+        if pieces.next()?.trim().parse::<u32>().ok()? > VERSION {
+            return None;
+        }
+        let mut format = Self::default();
+        for piece in pieces {
+            let (key, value) = piece.split_once(':')?;
+            let value = serde_yaml::Value::String(value.trim().to_owned());
+            match key.trim() {
+                "dates" => format.dates = serde_yaml::from_value(value).ok()?,
+                "durations" => format.durations = serde_yaml::from_value(value).ok()?,
+                "language" => format.language = serde_yaml::from_value(value).ok()?,
+                // Options from newer versions we don't know about yet.
+                _ => {}
+            }
+        }
+        Some(format)
+    }
+
+    pub fn format_date(&self, date: &NaiveDate) -> String {
+        date.format(self.date_pattern()).to_string()
+    }
+
+    pub fn parse_date(&self, s: &str) -> Option<NaiveDate> {
+        NaiveDate::parse_from_str(s, self.date_pattern()).ok()
+    }
+
+    fn date_pattern(&self) -> &'static str {
+        match self.dates {
+            DateStyle::Dotted => "%d.%m.%Y",
+            DateStyle::Iso => "%Y-%m-%d",
+            DateStyle::Us => "%m/%d/%Y",
+        }
+    }
+
+    pub fn format_duration(&self, duration: Duration) -> String {
+        match self.durations {
+            DurationStyle::Clock => util::format_duration(duration),
+            DurationStyle::Decimal => format!("{:.2}h", duration.num_seconds() as f64 / 3600.0),
+        }
+    }
+
+    pub fn parse_duration(&self, s: &str) -> Option<Duration> {
+        match self.durations {
+            DurationStyle::Clock => util::parse_duration(s),
+            DurationStyle::Decimal => {
+                let hours: f64 = s.strip_suffix('h')?.parse().ok()?;
+                Some(hours)
+                    .filter(|hours| hours.is_finite() && *hours >= 0.0)
+                    .map(|hours| Duration::seconds((hours * 3600.0).round() as i64))
+            }
+        }
+    }
+
+    /// The line with the total of a month.
+    pub fn total_line(&self, month: u32, year: i32, total: Duration) -> String {
+        format!(
+            "{} {} {} {}",
+            self.total_prefix(),
+            self.month_name(month),
+            year,
+            self.format_duration(total)
+        )
+    }
+
+    pub fn is_total_line(&self, line: &str) -> bool {
+        let line = line.to_lowercase();
+        line.starts_with("total") || line.starts_with(&self.total_prefix().to_lowercase())
+    }
+
+    fn total_prefix(&self) -> &'static str {
+        match self.language {
+            Language::En => "Total for",
+            Language::De => "Summe für",
+            Language::Fr => "Total pour",
+            Language::Es => "Total de",
+        }
+    }
+
+    pub fn month_name(&self, month: u32) -> &'static str {
+        let names = match self.language {
+            Language::En => [
+                "January",
+                "February",
+                "March",
+                "April",
+                "May",
+                "June",
+                "July",
+                "August",
+                "September",
+                "October",
+                "November",
+                "December",
+            ],
+            Language::De => [
+                "Januar",
+                "Februar",
+                "März",
+                "April",
+                "Mai",
+                "Juni",
+                "Juli",
+                "August",
+                "September",
+                "Oktober",
+                "November",
+                "Dezember",
+            ],
+            Language::Fr => [
+                "janvier",
+                "février",
+                "mars",
+                "avril",
+                "mai",
+                "juin",
+                "juillet",
+                "août",
+                "septembre",
+                "octobre",
+                "novembre",
+                "décembre",
+            ],
+            Language::Es => [
+                "enero",
+                "febrero",
+                "marzo",
+                "abril",
+                "mayo",
+                "junio",
+                "julio",
+                "agosto",
+                "septiembre",
+                "octubre",
+                "noviembre",
+                "diciembre",
+            ],
+        };
+        names[month as usize - 1]
+    }
+}
+
+/// The name of an option as written in the settings.
+fn name(option: &impl Serialize) -> String {
+    serde_yaml::to_string(option)
+        .unwrap()
+        .trim_start_matches("---")
+        .trim()
+        .to_owned()
+}
+
+fn write(path: &Path, content: &str) {
+    if let Err(e) = util::write_atomic(path, content) {
+        panic!("Could not write \"{}\": {}", path.to_str().unwrap(), e);
     }
 }
 
 fn report_path() -> PathBuf {
-    util::home_dir().join("hours.txt")
+    paths::report_file()
 }
 
 fn backup_report_path() -> PathBuf {
-    util::home_dir().join("hours.bak.txt")
+    paths::backup_report_file()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header() {
+        let format = ReportFormat {
+            dates: DateStyle::Iso,
+            durations: DurationStyle::Decimal,
+            language: Language::De,
+        };
+
+        let header = format.header();
+
+        assert_eq!(
+            header,
+            "# hours report v2, dates: iso, durations: decimal, language: de"
+        );
+        assert_eq!(ReportFormat::of(&Report(header)), Some(format));
+        assert_eq!(
+            ReportFormat::of(&Report("01.03.2021 01:00:00".to_owned())),
+            Some(ReportFormat::default())
+        );
+        assert_eq!(
+            ReportFormat::of(&Report("# hours report v3".to_owned())),
+            None
+        );
+        assert_eq!(
+            ReportFormat::of(&Report("# hours report v2, dates: julian".to_owned())),
+            None
+        );
+    }
+
+    #[test]
+    fn test_decimal_durations() {
+        let format = ReportFormat {
+            durations: DurationStyle::Decimal,
+            ..Default::default()
+        };
+
+        assert_eq!(format.format_duration(Duration::minutes(435)), "7.25h");
+        assert_eq!(format.parse_duration("7.25h"), Some(Duration::minutes(435)));
+        assert_eq!(format.parse_duration("7.25"), None);
+    }
 }
@@ -0,0 +1,432 @@
+use std::{
+    fs, io,
+    sync::{
+        mpsc::{self, RecvTimeoutError, Sender},
+        Arc, Mutex,
+    },
+    thread,
+    time::{self, Instant},
+};
+
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, NaiveTime};
+use serde::{Deserialize, Serialize};
+
+use crate::events::{Event, EventLog};
+use crate::{clock, paths, terminal, util};
+
+/// How long the wall clock has to run ahead of the monotonic clock between two checks before we
+/// assume the machine was suspended. Anything shorter is more likely a clock adjustment.
+const SUSPEND_THRESHOLD_SECS: i64 = 60;
+
+/// How many state file updates there are per heartbeat in the event log.
+const STATE_UPDATES_PER_HEARTBEAT: u32 = 20;
+
+/// A stretch of tracked time, booked on the day it started.
+///
+/// Time is measured on the wall clock, because the monotonic clock stops while the machine is
+/// suspended. Suspends are detected by comparing both clocks, see `check_suspend`. Everything
+/// that happens to the session is also written to the event log.
+pub struct Session {
+    pub date: NaiveDate,
+    pub project: Option<String>,
+    pub billable: bool,
+    started_at: DateTime<FixedOffset>,
+    paused_since: Option<DateTime<FixedOffset>>,
+    paused: Duration,
+    /// Suspended time that isn't counted.
+    excluded: Duration,
+    /// Suspends the user hasn't been asked about yet. Until they are, they aren't counted.
+    unconfirmed_suspends: Vec<Suspend>,
+    last_check: (Instant, DateTime<FixedOffset>),
+    log: EventLog,
+}
+
+/// A period the machine was suspended while the session was running.
+#[derive(Debug, Clone, Copy)]
+pub struct Suspend {
+    pub from: DateTime<FixedOffset>,
+    pub to: DateTime<FixedOffset>,
+}
+
+impl Suspend {
+    pub fn duration(&self) -> Duration {
+        self.to - self.from
+    }
+}
+
+/// What to do with time the machine spent suspended while the timer was running.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SuspendPolicy {
+    /// Ask when the timer is running in a terminal, don't count it in the daemon.
+    #[default]
+    Ask,
+    Count,
+    Exclude,
+}
+
+/// The running session, if any, shared with the thread writing heartbeats.
+pub type SharedSession = Arc<Mutex<Option<Session>>>;
+
+impl Session {
+    pub fn start(log: EventLog, project: Option<String>, billable: bool) -> Self {
+        Self::start_at(log, project, billable, clock::now())
+    }
+
+    fn start_at(
+        log: EventLog,
+        project: Option<String>,
+        billable: bool,
+        at: DateTime<FixedOffset>,
+    ) -> Self {
+        log.append(&Event::SessionStarted {
+            at,
+            project: project.clone(),
+            billable,
+        });
+        Self {
+            date: clock::date(at),
+            project,
+            billable,
+            started_at: at,
+            paused_since: None,
+            paused: Duration::zero(),
+            excluded: Duration::zero(),
+            unconfirmed_suspends: Vec::new(),
+            last_check: (Instant::now(), clock::now()),
+            log,
+        }
+    }
+
+    /// Time tracked so far, not counting pauses and excluded suspends.
+    pub fn elapsed(&self) -> Duration {
+        let end = self.paused_since.unwrap_or_else(clock::now);
+        end - self.started_at - self.paused - self.excluded
+    }
+
+    /// Suspended time that isn't counted.
+    pub fn excluded(&self) -> Duration {
+        self.excluded
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused_since.is_some()
+    }
+
+    /// Returns `false` if the session was already paused.
+    pub fn pause(&mut self) -> bool {
+        if self.is_paused() {
+            return false;
+        }
+        let now = clock::now();
+        self.paused_since = Some(now);
+        self.log.append(&Event::Paused { at: now });
+        true
+    }
+
+    /// Returns `false` if the session wasn't paused.
+    pub fn resume(&mut self) -> bool {
+        match self.paused_since.take() {
+            Some(paused_since) => {
+                let now = clock::now();
+                self.paused = self.paused + (now - paused_since);
+                self.log.append(&Event::Resumed { at: now });
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Checks whether the machine was suspended since the last check and handles it according
+    /// to `policy`. Has to be called regularly, at least every `SUSPEND_THRESHOLD_SECS`.
+    pub fn check_suspend(&mut self, policy: SuspendPolicy) {
+        let (last_instant, last_wall_clock) = self.last_check;
+        let now = (Instant::now(), clock::now());
+        self.last_check = now;
+        let monotonic = Duration::from_std(now.0 - last_instant).unwrap();
+        let gap = (now.1 - last_wall_clock) - monotonic;
+        // Time spent paused isn't counted anyway.
+        if gap < Duration::seconds(SUSPEND_THRESHOLD_SECS) || self.is_paused() {
+            return;
+        }
+        let suspend = Suspend {
+            from: last_wall_clock,
+            to: last_wall_clock + gap,
+        };
+        if policy == SuspendPolicy::Count {
+            return;
+        }
+        // Excluded suspends are recorded as a pause, so the event log agrees with `elapsed`.
+        self.excluded = self.excluded + suspend.duration();
+        self.log.append_all(&[
+            Event::Paused { at: suspend.from },
+            Event::Resumed { at: suspend.to },
+        ]);
+        if policy == SuspendPolicy::Ask {
+            self.unconfirmed_suspends.push(suspend);
+        }
+    }
+
+    /// Takes a suspend the user should be asked about, see `count_suspend`.
+    pub fn take_unconfirmed_suspend(&mut self) -> Option<Suspend> {
+        self.unconfirmed_suspends.pop()
+    }
+
+    /// Counts a suspend that was excluded so far, as part of the session.
+    pub fn count_suspend(&mut self, suspend: &Suspend) {
+        self.excluded = self.excluded - suspend.duration();
+        self.log.append(&Event::SuspendCounted {
+            from: suspend.from,
+            to: suspend.to,
+        });
+    }
+
+    /// Counts a completed pomodoro on the session's day.
+    pub fn complete_pomodoro(&self) {
+        self.log.append(&Event::Pomodoros {
+            date: self.date,
+            count: 1,
+        });
+    }
+
+    /// Stops the session, adding its time to the timesheet.
+    pub fn book(&self) {
+        self.log.append(&Event::Stopped { at: clock::now() });
+        self.log.save_projection();
+        SessionState::remove();
+    }
+
+    fn state(&self) -> SessionState {
+        SessionState {
+            date: self.date,
+            project: self.project.clone(),
+            started_at: self.started_at,
+            last_heartbeat: clock::now(),
+            elapsed_seconds: self.elapsed().num_seconds(),
+        }
+    }
+}
+
+/// The running session as last written to disk. It's updated more often than the heartbeats in
+/// the event log, so less time is lost when recovering an interrupted session.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionState {
+    pub date: NaiveDate,
+    pub project: Option<String>,
+    pub started_at: DateTime<FixedOffset>,
+    pub last_heartbeat: DateTime<FixedOffset>,
+    /// Time tracked as of the last heartbeat.
+    pub elapsed_seconds: i64,
+}
+
+impl SessionState {
+    pub fn load() -> Option<Self> {
+        let content = fs::read_to_string(paths::session_file()).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    fn save(&self) {
+        util::write_atomic(&paths::session_file(), serde_json::to_string(self).unwrap()).unwrap();
+    }
+
+    fn remove() {
+        match fs::remove_file(paths::session_file()) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => panic!("{}", e),
+            _ => {}
+        }
+    }
+}
+
+/// Deals with a session in the event log whose process died without stopping it. Has to be
+/// called with the lock held. Asks the user how to close the session if `interactive`, otherwise
+/// closes it at its last heartbeat. Returns the session if it should continue.
+pub fn recover_interrupted_session(log: &EventLog, interactive: bool) -> Option<Session> {
+    let open = log.open_session()?;
+    let last_heartbeat = match SessionState::load() {
+        Some(state) if state.started_at == open.start => state.last_heartbeat.max(open.end),
+        _ => open.end,
+    };
+    println!(
+        "A session started at {} was interrupted. It was last seen running at {}, with {} tracked.",
+        open.start.format("%H:%M:%S on %d.%m.%Y"),
+        last_heartbeat.format("%H:%M:%S on %d.%m.%Y"),
+        util::format_duration(open.duration_until(last_heartbeat))
+    );
+    let close = |at| {
+        log.append(&Event::Stopped { at });
+        log.save_projection();
+        SessionState::remove();
+    };
+    if !interactive {
+        println!("Closing it at the last heartbeat.");
+        close(last_heartbeat);
+        return None;
+    }
+    println!(
+        "  [1] Close it at {}\n  [2] Close it at another time\n  [3] Continue it, counting the time since {}",
+        last_heartbeat.format("%H:%M:%S"),
+        last_heartbeat.format("%H:%M:%S")
+    );
+    loop {
+        match terminal::prompt("Choice [1]: ").as_str() {
+            "" | "1" => {
+                close(last_heartbeat);
+                return None;
+            }
+            "2" => {
+                close(prompt_end_time(open.start, last_heartbeat));
+                return None;
+            }
+            "3" => {
+                // The interrupted part is closed, and the rest continues as a new session that
+                // started when the heartbeats stopped.
+                close(last_heartbeat);
+                return Some(Session::start_at(
+                    log.clone(),
+                    open.project,
+                    open.billable,
+                    last_heartbeat,
+                ));
+            }
+            _ => {}
+        }
+    }
+}
+
+fn prompt_end_time(
+    start: DateTime<FixedOffset>,
+    last_heartbeat: DateTime<FixedOffset>,
+) -> DateTime<FixedOffset> {
+    let prompt = format!("End time (HH:MM) [{}]: ", last_heartbeat.format("%H:%M"));
+    loop {
+        let answer = terminal::prompt(&prompt);
+        // An empty answer, which is also all that's read once stdin is closed, keeps the last
+        // heartbeat.
+        if answer.is_empty() {
+            return last_heartbeat;
+        }
+        let end = NaiveTime::parse_from_str(&answer, "%H:%M")
+            .ok()
+            .and_then(|time| last_heartbeat.date().and_time(time));
+        match end {
+            Some(end) if end >= start && end <= clock::now() => return end,
+            _ => println!("Enter a time between {} and now.", start.format("%H:%M")),
+        }
+    }
+}
+
+pub struct Cancel;
+
+/// Every few seconds, writes the session's state and checks whether the machine was suspended.
+/// Every minute, appends a heartbeat to the event log.
+pub fn write_heartbeats_in_background(
+    session: SharedSession,
+    suspend_policy: SuspendPolicy,
+) -> (thread::JoinHandle<()>, Sender<Cancel>) {
+    let (sender, receiver) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        let mut updates = 0;
+        while let Err(RecvTimeoutError::Timeout) =
+            receiver.recv_timeout(time::Duration::from_secs(3))
+        {
+            if let Some(session) = session.lock().unwrap().as_mut() {
+                session.check_suspend(suspend_policy);
+                let state = session.state();
+                state.save();
+                updates += 1;
+                if updates % STATE_UPDATES_PER_HEARTBEAT == 0 {
+                    session.log.append(&Event::Heartbeat {
+                        at: state.last_heartbeat,
+                    });
+                }
+            }
+        }
+    });
+    (handle, sender)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::timesheet::Timesheet;
+    use crate::util::TempDir;
+
+    use super::*;
+
+    fn temp_log(name: &str) -> (TempDir, EventLog) {
+        let dir = TempDir::new(&format!("session-{}", name));
+        let log = EventLog::at(dir.join("hours.events.jsonl"));
+        (dir, log)
+    }
+
+    /// Makes it look like the machine was suspended for `duration` just now.
+    fn simulate_suspend(session: &mut Session, duration: Duration) {
+        session.started_at = session.started_at - duration;
+        session.last_check.1 = session.last_check.1 - duration;
+    }
+
+    #[test]
+    fn test_suspend_excluded() {
+        let (_dir, log) = temp_log("excluded");
+        let mut session = Session::start(log.clone(), None, true);
+        simulate_suspend(&mut session, Duration::hours(2));
+
+        session.check_suspend(SuspendPolicy::Exclude);
+
+        assert!(session.elapsed() < Duration::seconds(1));
+        assert_eq!(session.take_unconfirmed_suspend().map(|_| ()), None);
+        assert_eq!(log.read().len(), 3);
+    }
+
+    #[test]
+    fn test_suspend_counted() {
+        let (_dir, log) = temp_log("counted");
+        let mut session = Session::start(log, None, true);
+        simulate_suspend(&mut session, Duration::hours(2));
+
+        session.check_suspend(SuspendPolicy::Count);
+
+        assert!(session.elapsed() >= Duration::hours(2));
+    }
+
+    #[test]
+    fn test_suspend_asked() {
+        let (_dir, log) = temp_log("asked");
+        let mut session = Session::start(log, None, true);
+        simulate_suspend(&mut session, Duration::hours(2));
+
+        session.check_suspend(SuspendPolicy::Ask);
+        assert!(session.elapsed() < Duration::seconds(1));
+        let suspend = session.take_unconfirmed_suspend().unwrap();
+        assert!(suspend.duration() >= Duration::hours(2) - Duration::seconds(1));
+        session.count_suspend(&suspend);
+
+        assert!(session.elapsed() >= Duration::hours(2) - Duration::seconds(1));
+    }
+
+    #[test]
+    fn test_short_clock_adjustment_ignored() {
+        let (_dir, log) = temp_log("adjustment");
+        let mut session = Session::start(log, None, true);
+        simulate_suspend(&mut session, Duration::seconds(5));
+
+        session.check_suspend(SuspendPolicy::Exclude);
+
+        assert!(session.elapsed() >= Duration::seconds(5));
+    }
+
+    #[test]
+    fn test_event_log_agrees_with_session() {
+        let (_dir, log) = temp_log("agrees");
+        let mut session = Session::start(log.clone(), None, true);
+        session.pause();
+        session.resume();
+        simulate_suspend(&mut session, Duration::hours(1));
+        session.check_suspend(SuspendPolicy::Exclude);
+        let elapsed = session.elapsed();
+        log.append(&Event::Stopped { at: clock::now() });
+
+        let booked = Timesheet::from_events(&log.read()).get_hours(&session.date);
+
+        assert!((booked - elapsed).num_milliseconds().abs() < 100);
+    }
+}
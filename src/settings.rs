@@ -1,23 +1,167 @@
 use std::fs;
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
 use std::path::PathBuf;
+use std::{env, io};
 
-use serde::Deserialize;
+use chrono::Duration;
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
 
-use crate::util;
+use crate::billing::Billing;
+use crate::compliance::ComplianceRules;
+use crate::credentials::{self, ApiKeySource, CredentialError, Secret};
+use crate::leave::LeaveSettings;
+use crate::overtime::OvertimeSettings;
+use crate::pdf::PdfSettings;
+use crate::pomodoro::PomodoroSettings;
+use crate::report::ReportFormat;
+use crate::session::SuspendPolicy;
+use crate::{paths, terminal, util};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct Settings {
-    pub api_key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<Secret>,
+    /// Name of an environment variable holding the API key.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key_env: Option<String>,
+    /// Shell command printing the API key, e.g. a password manager CLI.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key_command: Option<String>,
+    /// File holding the API key encrypted with a passphrase, see `hours encrypt-key`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key_file: Option<PathBuf>,
     pub gist_id: String,
+    /// Where the hours are kept, relative to the settings file unless absolute.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_file: Option<PathBuf>,
+    /// Whether time the machine spent suspended while the timer was running counts.
+    #[serde(default)]
+    pub suspended_time: SuspendPolicy,
+    /// The timezone like `Europe/Berlin` whose calendar days hours are booked on, so they don't
+    /// move when the machine's timezone changes. By default it's the machine's timezone.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<Tz>,
+    /// Minutes of unpaid lunch break added to the time you can leave at.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lunch_break: Option<u32>,
+    /// Break, daily maximum and rest rules, checked while the timer runs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compliance: Option<ComplianceRules>,
+    /// Hours to work per ISO week, like 38.5, for contracts with a weekly target.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weekly_target: Option<f64>,
+    /// How days off count, and the vacation allowance.
+    #[serde(default, skip_serializing_if = "LeaveSettings::is_default")]
+    pub leave: LeaveSettings,
+    /// The flexitime account shown in status and reports.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub overtime: Option<OvertimeSettings>,
+    /// The work and break lengths of `hours pomodoro`.
+    #[serde(default, skip_serializing_if = "PomodoroSettings::is_default")]
+    pub pomodoro: PomodoroSettings,
+    /// How the report file is written.
+    #[serde(default)]
+    pub report_format: ReportFormat,
+    /// Rates and rounding rules for `hours bill`.
+    #[serde(default, skip_serializing_if = "Billing::is_default")]
+    pub billing: Billing,
+    /// The header and signature lines of `hours pdf`.
+    #[serde(default, skip_serializing_if = "PdfSettings::is_default")]
+    pub pdf: PdfSettings,
+    #[serde(skip)]
+    api_key_source: Option<Result<ApiKeySource, CredentialError>>,
 }
 
 impl Settings {
     pub fn load() -> Self {
-        let content = fs::read_to_string(settings_path()).unwrap();
-        serde_yaml::from_str(&content).unwrap()
+        Self::try_load().unwrap_or_else(|| {
+            util::exit_with_error(format!(
+                "Settings file \"{}\" not found, run `hours init` to create it.",
+                paths::config_file().display()
+            ))
+        })
+    }
+
+    /// Like `load`, but returns `None` if there is no settings file.
+    pub fn try_load() -> Option<Self> {
+        let content = match fs::read_to_string(paths::config_file()) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return None,
+            Err(e) => panic!("{}", e),
+        };
+        let mut settings: Self = serde_yaml::from_str(&content).unwrap();
+        settings.api_key_source = Some(settings.resolve_api_key_source());
+        Some(settings)
+    }
+
+    pub fn weekly_target(&self) -> Option<Duration> {
+        self.weekly_target
+            .map(|hours| Duration::seconds((hours * 3600.0).round() as i64))
+    }
+
+    pub fn lunch_break(&self) -> Duration {
+        Duration::minutes(self.lunch_break.unwrap_or(0) as i64)
+    }
+
+    pub fn exists() -> bool {
+        paths::config_file().exists()
+    }
+
+    pub fn save(&self) {
+        let path = paths::config_file();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).unwrap();
+        }
+        // The file may contain the API key in plain text.
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(path)
+            .unwrap();
+        file.write_all(serde_yaml::to_string(self).unwrap().as_bytes())
+            .unwrap();
+    }
+
+    /// Fetches the API key from the configured source, asking for the passphrase if it's stored
+    /// in an encrypted file. Exits with an error message if the key can't be loaded.
+    pub fn api_key(&self) -> Secret {
+        match self.api_key_source.as_ref().unwrap() {
+            Ok(source) => source
+                .resolve(|| terminal::prompt_secret("Passphrase for the API key: "))
+                .unwrap_or_else(|e| fail(&e)),
+            Err(e) => fail(e),
+        }
+    }
+
+    fn resolve_api_key_source(&self) -> Result<ApiKeySource, CredentialError> {
+        let mut sources = Vec::new();
+        if let Some(api_key) = &self.api_key {
+            sources.push(ApiKeySource::Plain(api_key.clone()));
+        }
+        if let Some(var) = &self.api_key_env {
+            sources.push(ApiKeySource::Env(var.clone()));
+        }
+        if let Some(command) = &self.api_key_command {
+            sources.push(ApiKeySource::Command(command.clone()));
+        }
+        if let Some(path) = &self.api_key_file {
+            sources.push(ApiKeySource::EncryptedFile(util::expand_home(path)));
+        }
+        match sources.len() {
+            0 if env::var_os(credentials::DEFAULT_ENV_VAR).is_some() => {
+                Ok(ApiKeySource::Env(credentials::DEFAULT_ENV_VAR.to_owned()))
+            }
+            0 => Err(CredentialError::NoSource),
+            1 => Ok(sources.pop().unwrap()),
+            _ => Err(CredentialError::MultipleSources),
+        }
     }
 }
 
-fn settings_path() -> PathBuf {
-    util::home_dir().join(".config/hours.yaml")
+fn fail(e: &CredentialError) -> ! {
+    util::exit_with_error(format!("Could not load the API key: {}.", e))
 }
@@ -0,0 +1,299 @@
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+
+use crate::period::Period;
+use crate::remaining_work;
+use crate::timesheet::Timesheet;
+use crate::util;
+
+/// How many months the trend covers.
+const TREND_MONTHS: usize = 6;
+
+/// Days in a row with hours recorded. Weekends and leave don't interrupt a streak.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Streak {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub days: u32,
+}
+
+/// Statistics about a timesheet as of `today`.
+pub struct Stats<'a> {
+    timesheet: &'a Timesheet,
+    today: NaiveDate,
+}
+
+impl<'a> Stats<'a> {
+    pub fn new(timesheet: &'a Timesheet, today: NaiveDate) -> Self {
+        Self { timesheet, today }
+    }
+
+    fn this_month(&self) -> Period {
+        Period::month(self.today.year(), self.today.month())
+    }
+
+    /// The average per working day of `period`, counting the working days up to today.
+    pub fn average_per_working_day(&self, period: &Period) -> Option<Duration> {
+        match period.working_days_until(self.today) {
+            0 => None,
+            days => Some(period.hours(self.timesheet) / days),
+        }
+    }
+
+    /// The longest streak and the one still going on today, or the last working day.
+    pub fn streaks(&self) -> (Option<Streak>, Option<Streak>) {
+        let mut longest: Option<Streak> = None;
+        let mut current: Option<Streak> = None;
+        let mut last_date: Option<NaiveDate> = None;
+        for (date, hours) in self.timesheet.entries() {
+            if hours <= Duration::zero() {
+                continue;
+            }
+            current = match (current, last_date) {
+                (Some(streak), Some(last)) if self.only_days_off_between(last, date) => {
+                    Some(Streak {
+                        to: date,
+                        days: streak.days + 1,
+                        ..streak
+                    })
+                }
+                _ => Some(Streak {
+                    from: date,
+                    to: date,
+                    days: 1,
+                }),
+            };
+            if longest.is_none_or(|longest| current.unwrap().days > longest.days) {
+                longest = current;
+            }
+            last_date = Some(date);
+        }
+        // The last streak is still going if it includes today or only days off came since.
+        let current = current.filter(|streak| {
+            self.only_days_off_between(streak.to, self.today) || streak.to == self.today
+        });
+        (longest, current)
+    }
+
+    /// Whether all days after `from` and before `to` are weekends or taken off.
+    fn only_days_off_between(&self, from: NaiveDate, to: NaiveDate) -> bool {
+        let mut date = from.succ();
+        while date < to {
+            if remaining_work::is_working_day(date.weekday())
+                && self.timesheet.get_leave(date).is_none()
+            {
+                return false;
+            }
+            date = date.succ();
+        }
+        from < to
+    }
+
+    /// The average hours on the days of each weekday with hours recorded, and that weekday's share
+    /// of all hours.
+    pub fn weekdays(&self) -> Vec<(Weekday, Duration, f64)> {
+        let mut totals = [(Duration::zero(), 0); 7];
+        for (date, hours) in self
+            .timesheet
+            .entries()
+            .filter(|(_, h)| *h > Duration::zero())
+        {
+            let total = &mut totals[date.weekday().num_days_from_monday() as usize];
+            *total = (total.0 + hours, total.1 + 1);
+        }
+        let all: Duration = totals
+            .iter()
+            .fold(Duration::zero(), |acc, (hours, _)| acc + *hours);
+        let mut weekday = Weekday::Mon;
+        let mut result = Vec::new();
+        for &(hours, days) in totals.iter() {
+            if days > 0 {
+                let share = hours.num_seconds() as f64 / all.num_seconds() as f64;
+                result.push((weekday, hours / days, share));
+            }
+            weekday = weekday.succ();
+        }
+        result
+    }
+
+    /// The totals of the last months, oldest first, ending with this month.
+    pub fn months(&self) -> Vec<(Period, Duration)> {
+        let mut months = vec![self.this_month()];
+        while months.len() < TREND_MONTHS {
+            let previous = months[0].from.pred();
+            months.insert(0, Period::month(previous.year(), previous.month()));
+        }
+        months
+            .into_iter()
+            .map(|month| {
+                let hours = month.hours(self.timesheet);
+                (month, hours)
+            })
+            .collect()
+    }
+
+    /// The month's total if work continues at this month's pace so far.
+    pub fn month_end_projection(&self) -> Option<Duration> {
+        let month = self.this_month();
+        let pace = self.average_per_working_day(&month)?;
+        Some(pace * month.working_days_until(month.to))
+    }
+
+    pub fn summary(&self) -> String {
+        let month = self.this_month();
+        let mut lines = Vec::new();
+        if let Some(average) = self.average_per_working_day(&month) {
+            lines.push(format!(
+                "Average per working day in {}: {}",
+                month.name,
+                util::format_duration(average)
+            ));
+        }
+        match self.streaks() {
+            (Some(longest), current) => lines.push(format!(
+                "Longest streak: {} ({} to {}), current streak: {}",
+                days(longest.days),
+                longest.from.format("%d.%m.%Y"),
+                longest.to.format("%d.%m.%Y"),
+                days(current.map_or(0, |streak| streak.days))
+            )),
+            (None, _) => lines.push("No hours recorded yet.".to_owned()),
+        }
+        let weekdays: Vec<_> = self
+            .weekdays()
+            .into_iter()
+            .map(|(weekday, average, share)| {
+                format!(
+                    "{} {} ({:.0}%)",
+                    weekday,
+                    util::format_duration(average),
+                    share * 100.0
+                )
+            })
+            .collect();
+        if !weekdays.is_empty() {
+            lines.push(format!("By weekday: {}", weekdays.join(", ")));
+        }
+        let months = self.months();
+        let trend: Vec<_> = months
+            .iter()
+            .enumerate()
+            .map(|(i, (month, hours))| {
+                let change = match i.checked_sub(1).map(|i| months[i].1) {
+                    Some(previous) if previous > Duration::zero() => format!(
+                        " ({:+.0}%)",
+                        (hours.num_seconds() as f64 / previous.num_seconds() as f64 - 1.0) * 100.0
+                    ),
+                    _ => String::new(),
+                };
+                format!(
+                    "{} {}{}",
+                    month.from.format("%b"),
+                    util::format_duration(*hours),
+                    change
+                )
+            })
+            .collect();
+        lines.push(format!("Last months: {}", trend.join(", ")));
+        if let Some(projection) = self.month_end_projection() {
+            lines.push(projection_line(&month, projection));
+        }
+        lines.join("\n")
+    }
+}
+
+pub fn projection_line(month: &Period, projection: Duration) -> String {
+    format!(
+        "At this pace, {} ends at {} of the {} target.",
+        month.name,
+        util::format_duration(projection),
+        util::format_duration(month.target_until(month.to))
+    )
+}
+
+fn days(days: u32) -> String {
+    match days {
+        1 => "1 day".to_owned(),
+        days => format!("{} days", days),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::leave::LeaveKind;
+    use crate::report::Report;
+
+    use super::*;
+
+    fn sample_timesheet() -> Timesheet {
+        // September 2026 starts on a Tuesday.
+        Timesheet::parse_report(&Report(
+            "
+27.08.2026 04:00:00
+28.08.2026 04:00:00
+31.08.2026 08:00:00
+01.09.2026 08:00:00
+02.09.2026 06:00:00
+04.09.2026 10:00:00
+07.09.2026 06:00:00
+"
+            .to_owned(),
+        ))
+    }
+
+    #[test]
+    fn test_streaks() {
+        let timesheet = sample_timesheet();
+        let stats = Stats::new(&timesheet, NaiveDate::from_ymd(2026, 9, 8));
+
+        let (longest, current) = stats.streaks();
+
+        assert_eq!(
+            longest,
+            Some(Streak {
+                from: NaiveDate::from_ymd(2026, 8, 27),
+                to: NaiveDate::from_ymd(2026, 9, 2),
+                days: 5
+            })
+        );
+        assert_eq!(current.map(|streak| streak.days), Some(2));
+        let stats = Stats::new(&timesheet, NaiveDate::from_ymd(2026, 9, 9));
+        assert_eq!(stats.streaks().1, None);
+
+        // A day off keeps the streak going.
+        let mut timesheet = timesheet;
+        timesheet.set_leave(NaiveDate::from_ymd(2026, 9, 8), Some(LeaveKind::Vacation));
+        timesheet.add_hours(&NaiveDate::from_ymd(2026, 9, 9), &Duration::hours(8));
+        let stats = Stats::new(&timesheet, NaiveDate::from_ymd(2026, 9, 10));
+        assert_eq!(stats.streaks().1.map(|streak| streak.days), Some(3));
+    }
+
+    #[test]
+    fn test_weekdays() {
+        let timesheet = sample_timesheet();
+        let stats = Stats::new(&timesheet, NaiveDate::from_ymd(2026, 9, 8));
+
+        let weekdays = stats.weekdays();
+
+        assert_eq!(weekdays[0].0, Weekday::Mon);
+        assert_eq!(weekdays[0].1, Duration::hours(7));
+        assert!((weekdays[0].2 - 14.0 / 46.0).abs() < 1e-9);
+        assert_eq!(weekdays.len(), 5);
+    }
+
+    #[test]
+    fn test_month_end_projection() {
+        let timesheet = sample_timesheet();
+        // Six working days so far, 30 hours, 22 working days in September.
+        let stats = Stats::new(&timesheet, NaiveDate::from_ymd(2026, 9, 8));
+
+        assert_eq!(
+            stats.average_per_working_day(&stats.this_month()),
+            Some(Duration::hours(5))
+        );
+        assert_eq!(stats.month_end_projection(), Some(Duration::hours(110)));
+        let months = stats.months();
+        assert_eq!(months.len(), TREND_MONTHS);
+        assert_eq!(months[4].1, Duration::hours(16));
+        assert_eq!(months[5].1, Duration::hours(30));
+    }
+}
@@ -23,22 +23,116 @@ pub fn quit() {
 
 pub fn ctrlc() -> bool {
     if event::poll(time::Duration::from_millis(500)).unwrap() {
-        match event::read().unwrap() {
+        matches!(
+            event::read().unwrap(),
             Event::Key(KeyEvent {
                 code: KeyCode::Char('c'),
                 modifiers: KeyModifiers::CONTROL,
-            }) => true,
-            _ => false,
-        }
+            })
+        )
     } else {
         false
     }
 }
 
-pub fn draw_duration(duration: Duration) {
+/// Asks a yes/no question while in raw mode, answered with a single key press. Anything but `y`
+/// means no.
+pub fn ask(question: &str) -> bool {
+    let mut stdout = stdout();
+    write!(&mut stdout, "\r\n{} [y/N] ", question).unwrap();
+    stdout.flush().unwrap();
+    let answer = loop {
+        if let Event::Key(KeyEvent { code, .. }) = event::read().unwrap() {
+            match code {
+                KeyCode::Char('y') | KeyCode::Char('Y') => break true,
+                KeyCode::Char(_) | KeyCode::Enter | KeyCode::Esc => break false,
+                _ => {}
+            }
+        }
+    };
+    write!(&mut stdout, "{}\r\n", if answer { "y" } else { "n" }).unwrap();
+    stdout.flush().unwrap();
+    answer
+}
+
+/// Draws the duration over the current line, followed by `note` if there is one.
+pub fn draw_duration(duration: Duration, note: Option<&str>) {
     let duration = util::format_duration(duration);
     let mut stdout = stdout();
     stdout.queue(cursor::MoveToColumn(0)).unwrap();
-    write!(&mut stdout, "{}", duration).unwrap();
+    match note {
+        Some(note) => write!(&mut stdout, "{}  {}", duration, note).unwrap(),
+        None => write!(&mut stdout, "{}", duration).unwrap(),
+    }
+    stdout
+        .queue(terminal::Clear(terminal::ClearType::UntilNewLine))
+        .unwrap();
+    stdout.flush().unwrap();
+}
+
+pub fn bell() {
+    let mut stdout = stdout();
+    write!(&mut stdout, "\x07").unwrap();
+    stdout.flush().unwrap();
+}
+
+/// Asks for a line of input and returns it trimmed.
+pub fn prompt(prompt: &str) -> String {
+    let mut stdout = stdout();
+    write!(&mut stdout, "{}", prompt).unwrap();
     stdout.flush().unwrap();
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer).unwrap();
+    answer.trim().to_owned()
+}
+
+/// Whether stdin is a terminal the user can answer questions on.
+pub fn is_interactive() -> bool {
+    unsafe { libc::isatty(libc::STDIN_FILENO) == 1 }
+}
+
+/// Whether stdout is a terminal, so colors can be used.
+pub fn is_color_output() -> bool {
+    unsafe { libc::isatty(libc::STDOUT_FILENO) == 1 }
+}
+
+/// Asks a yes/no question, `default` is used if the answer is empty.
+pub fn confirm(question: &str, default: bool) -> bool {
+    let options = if default { "[Y/n]" } else { "[y/N]" };
+    match prompt(&format!("{} {} ", question, options))
+        .to_lowercase()
+        .as_str()
+    {
+        "" => default,
+        answer => answer.starts_with('y'),
+    }
+}
+
+/// Reads a line from the terminal without echoing it, for tokens and passphrases.
+pub fn prompt_secret(prompt: &str) -> String {
+    let mut stdout = stdout();
+    write!(&mut stdout, "{}", prompt).unwrap();
+    stdout.flush().unwrap();
+    terminal::enable_raw_mode().unwrap();
+    let mut secret = String::new();
+    loop {
+        if let Event::Key(KeyEvent { code, modifiers }) = event::read().unwrap() {
+            match code {
+                KeyCode::Enter => break,
+                KeyCode::Backspace => {
+                    secret.pop();
+                }
+                KeyCode::Char('c') if modifiers == KeyModifiers::CONTROL => {
+                    terminal::disable_raw_mode().unwrap();
+                    println!();
+                    std::process::exit(130);
+                }
+                KeyCode::Char(c) => secret.push(c),
+                _ => {}
+            }
+        }
+    }
+    terminal::disable_raw_mode().unwrap();
+    println!();
+    secret
 }
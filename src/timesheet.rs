@@ -1,76 +1,444 @@
-use chrono::{Date, Datelike, Duration, Local, Month, TimeZone};
-use num_traits::cast::FromPrimitive;
+use std::fmt;
 
-use crate::{remaining_work::RemainingWork, report::Report, util};
+use chrono::{Datelike, Duration, NaiveDate};
 
+use crate::events::{self, Event, EventLog};
+use crate::leave::LeaveKind;
+use crate::period::Period;
+use crate::pomodoro;
+use crate::remaining_work::RemainingWork;
+use crate::report::{Report, ReportFormat};
+
+const CLOSED_PREFIX: &str = "# closed ";
+
+#[derive(Default)]
 pub struct Timesheet {
-    entries: Vec<(Date<Local>, Duration)>,
+    entries: Vec<(NaiveDate, Duration)>,
+    leave: Vec<(NaiveDate, LeaveKind)>,
+    /// Pomodoros completed per day, see `hours pomodoro`.
+    pomodoros: Vec<(NaiveDate, u32)>,
+    closings: Vec<Closing>,
+}
+
+/// A month closed with `hours close`, with a checksum of its hours at the time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Closing {
+    pub year: i32,
+    pub month: u32,
+    pub checksum: String,
+}
+
+impl Closing {
+    pub fn period(&self) -> Period {
+        Period::month(self.year, self.month)
+    }
+
+    /// The line recording the closing in the report, like `# closed 2026-09 sha256:…`.
+    fn report_line(&self) -> String {
+        format!(
+            "{}{}-{:02} {}",
+            CLOSED_PREFIX, self.year, self.month, self.checksum
+        )
+    }
+
+    fn parse_report_line(line: &str) -> Option<Self> {
+        let (month, checksum) = line.strip_prefix(CLOSED_PREFIX)?.split_once(' ')?;
+        let (year, month) = month.split_once('-')?;
+        Some(Self {
+            year: year.parse().ok()?,
+            month: month
+                .parse()
+                .ok()
+                .filter(|month| (1..=12).contains(month))?,
+            checksum: checksum.trim().to_owned(),
+        })
+    }
+}
+
+/// A change to a closed month that wasn't forced.
+#[derive(Debug)]
+pub struct ClosedMonthError {
+    pub month: Period,
+}
+
+impl fmt::Display for ClosedMonthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} is closed", self.month.name)
+    }
+}
+
+#[derive(Debug)]
+pub struct ParseError {
+    line_number: usize,
+    line: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid line {}: \"{}\"", self.line_number, self.line)
+    }
 }
 
 impl Timesheet {
+    /// Loads the timesheet from the event log, or from the report file if there is no event log
+    /// yet.
+    pub fn load() -> Self {
+        let log = EventLog::open();
+        if log.exists() {
+            Self::from_events(&log.read())
+        } else {
+            Self::parse_report(&Report::load())
+        }
+    }
+
+    /// The hours worked according to `events`. Sessions that are still open aren't included.
+    pub fn from_events(events: &[Event]) -> Self {
+        let mut timesheet = Self::default();
+        for session in events::sessions(events).iter().filter(|s| !s.open) {
+            timesheet.add_hours(&session.date(), &session.duration());
+        }
+        for event in events {
+            if let Event::Adjusted { date, seconds, .. } = event {
+                let date = *date;
+                timesheet.add_hours(&date, &Duration::seconds(*seconds));
+            }
+        }
+        for event in events {
+            match event {
+                Event::Pomodoros { date, count } => timesheet.add_pomodoros(*date, *count),
+                Event::Leave { date, kind } => timesheet.set_leave(*date, Some(*kind)),
+                Event::LeaveRemoved { date } => timesheet.set_leave(*date, None),
+                Event::MonthClosed {
+                    year,
+                    month,
+                    checksum,
+                    ..
+                } => timesheet.add_closing(Closing {
+                    year: *year,
+                    month: *month,
+                    checksum: checksum.clone(),
+                }),
+                _ => {}
+            }
+        }
+        timesheet
+    }
+
     pub fn parse_report(report: &Report) -> Self {
-        let mut entries = Vec::new();
-        for line in report.0.split("\n") {
+        Self::try_parse_report(report).unwrap_or_else(|e| panic!("Could not parse report: {}", e))
+    }
+
+    pub fn try_parse_report(report: &Report) -> Result<Self, ParseError> {
+        let mut timesheet = Self::default();
+        let mut format = None;
+        for (i, line) in report.0.split('\n').enumerate() {
             let line = line.trim();
-            if line == "" || line.to_lowercase().starts_with("total") {
+            let error = || ParseError {
+                line_number: i + 1,
+                line: line.to_owned(),
+            };
+            if line.is_empty() {
+                continue;
+            }
+            // The header, if there is one, is the first line.
+            let format = match format {
+                Some(format) => format,
+                None => *format.insert(ReportFormat::of(report).ok_or_else(error)?),
+            };
+            if let Some(closing) = Closing::parse_report_line(line) {
+                timesheet.add_closing(closing);
+                continue;
+            }
+            if line.starts_with('#') || format.is_total_line(line) {
                 continue;
             }
-            let mut pieces = line.split(" ");
-            let date = parse_date(pieces.next().unwrap());
-            let duration = util::parse_duration(pieces.next().unwrap());
-            entries.push((date, duration));
+            let mut pieces = line.split(' ');
+            let date = pieces.next().and_then(|s| format.parse_date(s));
+            let value = pieces.next().unwrap_or_default();
+            let rest: Vec<_> = pieces.collect();
+            match (date, format.parse_duration(value), rest.as_slice()) {
+                (Some(date), Some(duration), []) => timesheet.entries.push((date, duration)),
+                // Like `19.10.2026 03:12:45 4 pomodoros`.
+                (Some(date), Some(duration), [count, "pomodoro" | "pomodoros"]) => {
+                    let count: i32 = count.parse().map_err(|_| error())?;
+                    timesheet.entries.push((date, duration));
+                    timesheet.add_pomodoros(date, count);
+                }
+                (Some(date), None, []) => {
+                    let kind = LeaveKind::parse(value).ok_or_else(error)?;
+                    timesheet.set_leave(date, Some(kind));
+                }
+                _ => return Err(error()),
+            }
         }
-        Self { entries }
+        Ok(timesheet)
     }
 
-    pub fn generate_report(&self) -> Report {
-        let mut lines = Vec::new();
+    pub fn generate_report(&self, format: &ReportFormat) -> Report {
+        let mut lines = vec![format.header()];
         let mut total = Duration::seconds(0);
-        for (i, &(date, duration)) in self.entries.iter().enumerate() {
-            total = total + duration;
-            lines.push(format!(
-                "{} {}",
-                format_date(&date),
-                util::format_duration(duration)
-            ));
-            if i == self.entries.len() - 1 || date.month() != self.entries[i + 1].0.month() {
-                lines.push(format!(
-                    "Total for {} {} {}\n",
-                    Month::from_u32(date.month()).unwrap().name(),
-                    date.year(),
-                    util::format_duration(total)
-                ));
+        let dates = self.dates();
+        for (i, &date) in dates.iter().enumerate() {
+            if let Ok(entry) = self.binary_search(&date) {
+                let duration = self.entries[entry].1;
+                total = total + duration;
+                let mut line = format!(
+                    "{} {}",
+                    format.format_date(&date),
+                    format.format_duration(duration)
+                );
+                let pomodoros = self.get_pomodoros(date);
+                if pomodoros > 0 {
+                    line.push(' ');
+                    line.push_str(&pomodoro::count(pomodoros));
+                }
+                lines.push(line);
+            }
+            if let Some(kind) = self.get_leave(date) {
+                lines.push(format!("{} {}", format.format_date(&date), kind.name()));
+            }
+            if i == dates.len() - 1 || date.month() != dates[i + 1].month() {
+                lines.push(format.total_line(date.month(), date.year(), total));
+                if let Some(closing) = self.closing(date.year(), date.month()) {
+                    lines.push(closing.report_line());
+                }
+                lines.last_mut().unwrap().push('\n');
                 total = Duration::seconds(0);
             }
         }
-        return Report(lines.join("\n"));
+        // Months closed without any days.
+        for closing in &self.closings {
+            if !dates.iter().any(|date| closing.period().contains(*date)) {
+                lines.push(closing.report_line() + "\n");
+            }
+        }
+        if dates.is_empty() && self.closings.is_empty() {
+            lines.push(String::new());
+        }
+        Report(lines.join("\n"))
+    }
+
+    /// The dates with hours or leave, in order.
+    pub fn dates(&self) -> Vec<NaiveDate> {
+        let mut dates: Vec<_> = self
+            .entries()
+            .map(|(date, _)| date)
+            .chain(self.leave().map(|(date, _)| date))
+            .collect();
+        dates.sort();
+        dates.dedup();
+        dates
     }
 
-    pub fn add_hours(&mut self, date: &Date<Local>, duration: &Duration) {
+    pub fn add_hours(&mut self, date: &NaiveDate, duration: &Duration) {
         match self.binary_search(date) {
             Ok(i) => self.entries[i].1 = self.entries[i].1 + *duration,
-            Err(i) => self.entries.insert(i, (date.clone(), duration.clone())),
+            Err(i) => self.entries.insert(i, (*date, *duration)),
         };
     }
 
-    pub fn get_hours(&self, date: &Date<Local>) -> Duration {
+    pub fn entries(&self) -> impl Iterator<Item = (NaiveDate, Duration)> + '_ {
+        self.entries.iter().copied()
+    }
+
+    /// The dates with hours in either timesheet, in order.
+    pub fn dates_with(&self, other: &Timesheet) -> Vec<NaiveDate> {
+        let mut dates: Vec<_> = self
+            .entries()
+            .chain(other.entries())
+            .map(|(d, _)| d)
+            .collect();
+        dates.sort();
+        dates.dedup();
+        dates
+    }
+
+    pub fn get_hours(&self, date: &NaiveDate) -> Duration {
         match self.binary_search(date) {
             Ok(i) => self.entries[i].1,
             Err(_) => Duration::seconds(0),
         }
     }
 
+    pub fn get_pomodoros(&self, date: NaiveDate) -> u32 {
+        self.pomodoros
+            .iter()
+            .find(|(d, _)| *d == date)
+            .map_or(0, |(_, count)| *count)
+    }
+
+    /// Adds `count` pomodoros to the day, or removes them if it's negative.
+    pub fn add_pomodoros(&mut self, date: NaiveDate, count: i32) {
+        let count = (self.get_pomodoros(date) as i32 + count).max(0) as u32;
+        self.pomodoros.retain(|(d, _)| *d != date);
+        if count > 0 {
+            self.pomodoros.push((date, count));
+            self.pomodoros.sort();
+        }
+    }
+
+    /// The days taken off, in order.
+    pub fn leave(&self) -> impl Iterator<Item = (NaiveDate, LeaveKind)> + '_ {
+        self.leave.iter().copied()
+    }
+
+    pub fn get_leave(&self, date: NaiveDate) -> Option<LeaveKind> {
+        self.leave
+            .iter()
+            .find(|(d, _)| *d == date)
+            .map(|(_, kind)| *kind)
+    }
+
+    /// Records the day as taken off, or with `None`, as a normal day.
+    pub fn set_leave(&mut self, date: NaiveDate, kind: Option<LeaveKind>) {
+        self.leave.retain(|(d, _)| *d != date);
+        if let Some(kind) = kind {
+            self.leave.push((date, kind));
+            self.leave.sort();
+        }
+    }
+
+    /// A copy with the leave, pomodoros and the closed months, but no hours.
+    pub fn without_hours(&self) -> Self {
+        Self {
+            entries: Vec::new(),
+            leave: self.leave.clone(),
+            pomodoros: self.pomodoros.clone(),
+            closings: self.closings.clone(),
+        }
+    }
+
+    pub fn closing(&self, year: i32, month: u32) -> Option<&Closing> {
+        self.closings
+            .iter()
+            .find(|closing| closing.year == year && closing.month == month)
+    }
+
+    pub fn closings(&self) -> &[Closing] {
+        &self.closings
+    }
+
+    /// Closes the month, or closes it again with its current hours.
+    pub fn close(&mut self, year: i32, month: u32) -> Closing {
+        let closing = Closing {
+            year,
+            month,
+            checksum: self.checksum(year, month),
+        };
+        self.add_closing(closing.clone());
+        closing
+    }
+
+    fn add_closing(&mut self, closing: Closing) {
+        self.closings
+            .retain(|c| (c.year, c.month) != (closing.year, closing.month));
+        self.closings.push(closing);
+        self.closings.sort_by_key(|c| (c.year, c.month));
+    }
+
+    /// A SHA-256 of the month's days with hours, in whole seconds, followed by its leave and its
+    /// pomodoros.
+    pub fn checksum(&self, year: i32, month: u32) -> String {
+        let period = Period::month(year, month);
+        let mut content = String::new();
+        for (date, hours) in self.entries() {
+            if period.contains(date) && hours.num_seconds() != 0 {
+                content.push_str(&format!(
+                    "{} {}\n",
+                    date.format("%Y-%m-%d"),
+                    hours.num_seconds()
+                ));
+            }
+        }
+        for (date, kind) in self.leave().filter(|(date, _)| period.contains(*date)) {
+            content.push_str(&format!("{} {}\n", date.format("%Y-%m-%d"), kind.name()));
+        }
+        // Only there if the month has pomodoros, so the checksums of others stay the same.
+        for &(date, count) in self
+            .pomodoros
+            .iter()
+            .filter(|(date, _)| period.contains(*date))
+        {
+            content.push_str(&format!(
+                "{} {} pomodoros\n",
+                date.format("%Y-%m-%d"),
+                count
+            ));
+        }
+        let hash = openssl::sha::sha256(content.as_bytes());
+        let hex: String = hash.iter().map(|byte| format!("{:02x}", byte)).collect();
+        format!("sha256:{}", hex)
+    }
+
+    /// Whether the hours of a closed month are still the ones it was closed with.
+    pub fn is_intact(&self, closing: &Closing) -> bool {
+        self.checksum(closing.year, closing.month) == closing.checksum
+    }
+
+    /// Fails if going from this timesheet to `target` changes the hours, leave or pomodoros of a
+    /// closed month.
+    pub fn check_closed(&self, target: &Timesheet) -> Result<(), ClosedMonthError> {
+        let mut dates = self.dates();
+        dates.extend(target.dates());
+        dates.extend(
+            self.pomodoros
+                .iter()
+                .chain(&target.pomodoros)
+                .map(|(d, _)| *d),
+        );
+        dates.sort();
+        dates.dedup();
+        for date in dates {
+            let changed = self.get_hours(&date).num_seconds()
+                != target.get_hours(&date).num_seconds()
+                || self.get_leave(date) != target.get_leave(date)
+                || self.get_pomodoros(date) != target.get_pomodoros(date);
+            if let Some(closing) = self.closing(date.year(), date.month()).filter(|_| changed) {
+                return Err(ClosedMonthError {
+                    month: closing.period(),
+                });
+            }
+        }
+        Ok(())
+    }
+
     pub fn remaining_work(&self) -> Option<RemainingWork> {
         self.entries.last().map(|&(last, _)| {
+            let month = Period::month(last.year(), last.month());
             RemainingWork::new(
                 last,
-                Duration::hours(160) - self.hours_worked_in_month(last.month()),
+                monthly_target()
+                    - self.hours_worked_in_month(last.month())
+                    - month.leave_until(self, month.to),
             )
+            .excluding(self.days_off(&month))
         })
     }
 
-    fn binary_search(&self, date: &Date<Local>) -> Result<usize, usize> {
-        self.entries.binary_search_by(|&(d, _)| d.cmp(&date))
+    /// The work left in the month as of the start of `date`, so the day's own hours count as
+    /// not done yet.
+    pub fn remaining_work_from(&self, date: NaiveDate) -> RemainingWork {
+        let month = Period::month(date.year(), date.month());
+        let done = self
+            .entries()
+            .filter(|(d, _)| month.contains(*d) && *d < date)
+            .fold(Duration::zero(), |acc, (_, duration)| acc + duration);
+        RemainingWork::new(
+            date,
+            monthly_target() - done - month.leave_until(self, month.to),
+        )
+        .excluding(self.days_off(&month))
+    }
+
+    fn days_off(&self, period: &Period) -> Vec<NaiveDate> {
+        self.leave()
+            .filter(|(date, _)| period.contains(*date))
+            .map(|(date, _)| date)
+            .collect()
+    }
+
+    fn binary_search(&self, date: &NaiveDate) -> Result<usize, usize> {
+        self.entries.binary_search_by(|&(d, _)| d.cmp(date))
     }
 
     fn hours_worked_in_month(&self, month: u32) -> Duration {
@@ -82,20 +450,16 @@ impl Timesheet {
     }
 }
 
-fn parse_date(s: &str) -> Date<Local> {
-    let mut pieces = s.split(".");
-    let day: u32 = pieces.next().unwrap().parse().unwrap();
-    let month: u32 = pieces.next().unwrap().parse().unwrap();
-    let year: i32 = pieces.next().unwrap().parse().unwrap();
-    Local.ymd(year, month, day)
-}
-
-fn format_date(d: &Date<Local>) -> String {
-    format!("{:0>2}.{:0>2}.{}", d.day(), d.month(), d.year())
+/// The hours to work per month.
+pub fn monthly_target() -> Duration {
+    Duration::hours(160)
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::remaining_work::IncludeToday;
+    use crate::report::{DateStyle, DurationStyle, Language};
+
     use super::*;
 
     #[test]
@@ -105,6 +469,20 @@ mod tests {
         assert_eq!(timesheet.entries.len(), 0);
     }
 
+    #[test]
+    fn test_parse_invalid_report() {
+        let cases = [
+            "01.03.2021 01:00:00\n02.03.2021 01:1",
+            "01.03.2021 01:00:00\n02.03.2",
+            "31.02.2021 01:00:00",
+            "01.03.2021 01:00:00 extra",
+        ];
+
+        for &report in cases.iter() {
+            assert!(Timesheet::try_parse_report(&Report(report.to_owned())).is_err());
+        }
+    }
+
     #[test]
     fn test_timesheet_reporting() {
         let cases = vec![
@@ -115,9 +493,10 @@ Total for February 2021 05:00:12
 ",
                 Timesheet {
                     entries: vec![(
-                        Local.ymd(2021, 2, 3),
+                        NaiveDate::from_ymd(2021, 2, 3),
                         Duration::hours(5) + Duration::seconds(12),
                     )],
+                    ..Timesheet::default()
                 },
             ),
             (
@@ -134,26 +513,27 @@ Total for February 2021 26:18:49
                 Timesheet {
                     entries: vec![
                         (
-                            Local.ymd(2021, 1, 1),
+                            NaiveDate::from_ymd(2021, 1, 1),
                             Duration::hours(7) + Duration::seconds(12),
                         ),
                         (
-                            Local.ymd(2021, 2, 1),
+                            NaiveDate::from_ymd(2021, 2, 1),
                             Duration::hours(7) + Duration::seconds(12),
                         ),
                         (
-                            Local.ymd(2021, 2, 2),
+                            NaiveDate::from_ymd(2021, 2, 2),
                             Duration::hours(9) + Duration::minutes(17) + Duration::seconds(12),
                         ),
                         (
-                            Local.ymd(2021, 2, 3),
+                            NaiveDate::from_ymd(2021, 2, 3),
                             Duration::hours(5) + Duration::seconds(12),
                         ),
                         (
-                            Local.ymd(2021, 2, 11),
+                            NaiveDate::from_ymd(2021, 2, 11),
                             Duration::hours(5) + Duration::minutes(1) + Duration::seconds(13),
                         ),
                     ],
+                    ..Timesheet::default()
                 },
             ),
             (
@@ -171,30 +551,31 @@ Total for February 2021 26:18:49
                 Timesheet {
                     entries: vec![
                         (
-                            Local.ymd(2021, 1, 1),
+                            NaiveDate::from_ymd(2021, 1, 1),
                             Duration::hours(7) + Duration::seconds(12),
                         ),
                         (
-                            Local.ymd(2021, 1, 4),
+                            NaiveDate::from_ymd(2021, 1, 4),
                             Duration::hours(7) + Duration::seconds(12),
                         ),
                         (
-                            Local.ymd(2021, 2, 1),
+                            NaiveDate::from_ymd(2021, 2, 1),
                             Duration::hours(7) + Duration::seconds(12),
                         ),
                         (
-                            Local.ymd(2021, 2, 2),
+                            NaiveDate::from_ymd(2021, 2, 2),
                             Duration::hours(9) + Duration::minutes(17) + Duration::seconds(12),
                         ),
                         (
-                            Local.ymd(2021, 2, 3),
+                            NaiveDate::from_ymd(2021, 2, 3),
                             Duration::hours(5) + Duration::seconds(12),
                         ),
                         (
-                            Local.ymd(2021, 2, 11),
+                            NaiveDate::from_ymd(2021, 2, 11),
                             Duration::hours(5) + Duration::minutes(1) + Duration::seconds(13),
                         ),
                     ],
+                    ..Timesheet::default()
                 },
             ),
             (
@@ -208,22 +589,23 @@ Total for February 2021 26:18:49
                 Timesheet {
                     entries: vec![
                         (
-                            Local.ymd(2021, 2, 1),
+                            NaiveDate::from_ymd(2021, 2, 1),
                             Duration::hours(7) + Duration::seconds(12),
                         ),
                         (
-                            Local.ymd(2021, 2, 2),
+                            NaiveDate::from_ymd(2021, 2, 2),
                             Duration::hours(9) + Duration::minutes(17) + Duration::seconds(12),
                         ),
                         (
-                            Local.ymd(2021, 2, 3),
+                            NaiveDate::from_ymd(2021, 2, 3),
                             Duration::hours(5) + Duration::seconds(12),
                         ),
                         (
-                            Local.ymd(2021, 2, 11),
+                            NaiveDate::from_ymd(2021, 2, 11),
                             Duration::hours(5) + Duration::minutes(1) + Duration::seconds(13),
                         ),
                     ],
+                    ..Timesheet::default()
                 },
             ),
             (
@@ -237,24 +619,25 @@ Total for March 2021 06:16:01
 ",
                 Timesheet {
                     entries: vec![
-                        (Local.ymd(2021, 3, 1), Duration::hours(1)),
+                        (NaiveDate::from_ymd(2021, 3, 1), Duration::hours(1)),
                         (
-                            Local.ymd(2021, 3, 2),
+                            NaiveDate::from_ymd(2021, 3, 2),
                             Duration::hours(1) + Duration::minutes(14),
                         ),
                         (
-                            Local.ymd(2021, 3, 3),
+                            NaiveDate::from_ymd(2021, 3, 3),
                             Duration::hours(2) + Duration::seconds(1),
                         ),
                         (
-                            Local.ymd(2021, 3, 11),
+                            NaiveDate::from_ymd(2021, 3, 11),
                             Duration::hours(1) + Duration::minutes(1),
                         ),
                         (
-                            Local.ymd(2021, 3, 31),
+                            NaiveDate::from_ymd(2021, 3, 31),
                             Duration::hours(1) + Duration::minutes(1),
                         ),
                     ],
+                    ..Timesheet::default()
                 },
             ),
         ];
@@ -265,7 +648,10 @@ Total for March 2021 06:16:01
             let timesheet = Timesheet::parse_report(&Report(report.to_owned()));
 
             assert_eq!(timesheet.entries, expected_timesheet.entries);
-            assert_eq!(timesheet.generate_report().0.trim(), report.trim());
+            assert_eq!(
+                timesheet.generate_report(&ReportFormat::default()).0.trim(),
+                format!("{}\n{}", ReportFormat::default().header(), report.trim())
+            );
         }
     }
 
@@ -274,14 +660,15 @@ Total for March 2021 06:16:01
         let mut timesheet = create_sample_timesheet();
 
         timesheet.add_hours(
-            &Local.ymd(2021, 3, 11),
+            &NaiveDate::from_ymd(2021, 3, 11),
             &(Duration::hours(2) + Duration::minutes(12)),
         );
 
-        let report = timesheet.generate_report();
+        let report = timesheet.generate_report(&ReportFormat::default());
         assert_eq!(
             report.0,
-            "01.03.2021 01:00:00
+            "# hours report v2, dates: dotted, durations: clock, language: en
+01.03.2021 01:00:00
 02.03.2021 01:14:00
 03.03.2021 02:00:01
 11.03.2021 03:13:00
@@ -296,14 +683,15 @@ Total for March 2021 08:28:01
         let mut timesheet = create_sample_timesheet();
 
         timesheet.add_hours(
-            &Local.ymd(2021, 3, 12),
+            &NaiveDate::from_ymd(2021, 3, 12),
             &(Duration::hours(2) + Duration::minutes(12)),
         );
 
-        let report = timesheet.generate_report();
+        let report = timesheet.generate_report(&ReportFormat::default());
         assert_eq!(
             report.0,
-            "01.03.2021 01:00:00
+            "# hours report v2, dates: dotted, durations: clock, language: en
+01.03.2021 01:00:00
 02.03.2021 01:14:00
 03.03.2021 02:00:01
 11.03.2021 01:01:00
@@ -314,6 +702,116 @@ Total for March 2021 08:28:01
         );
     }
 
+    #[test]
+    fn test_report_format() {
+        let format = ReportFormat {
+            dates: DateStyle::Iso,
+            durations: DurationStyle::Decimal,
+            language: Language::De,
+        };
+        let timesheet = create_sample_timesheet();
+
+        let report = timesheet.generate_report(&format);
+
+        assert_eq!(
+            report.0,
+            "# hours report v2, dates: iso, durations: decimal, language: de
+2021-03-01 1.00h
+2021-03-02 1.23h
+2021-03-03 2.00h
+2021-03-11 1.02h
+2021-03-31 1.02h
+Summe für März 2021 6.27h
+"
+        );
+        let parsed = Timesheet::parse_report(&report);
+        assert_eq!(
+            parsed.get_hours(&NaiveDate::from_ymd(2021, 3, 2)),
+            Duration::hours(1) + Duration::seconds(828)
+        );
+        assert_eq!(parsed.generate_report(&format).0, report.0);
+    }
+
+    #[test]
+    fn test_closed_month() {
+        let mut timesheet = create_sample_timesheet();
+
+        let closing = timesheet.close(2021, 3);
+
+        assert_eq!(
+            closing.checksum,
+            "sha256:c955eebd76fb93abe34c49563f531ffb1f4b5ec968145306337567102b0c02da"
+        );
+        let report = timesheet.generate_report(&ReportFormat::default());
+        assert!(report.0.ends_with(&format!(
+            "Total for March 2021 06:16:01\n# closed 2021-03 {}\n",
+            closing.checksum
+        )));
+        let parsed = Timesheet::parse_report(&report);
+        assert_eq!(parsed.closings(), std::slice::from_ref(&closing));
+        assert!(parsed.is_intact(&closing));
+        let mut changed = Timesheet::parse_report(&report);
+        changed.add_hours(&NaiveDate::from_ymd(2021, 3, 2), &Duration::minutes(1));
+        assert!(!changed.is_intact(&closing));
+        assert_eq!(
+            parsed.check_closed(&changed).unwrap_err().month.name,
+            "March 2021"
+        );
+        changed.add_hours(&NaiveDate::from_ymd(2021, 4, 1), &Duration::hours(1));
+        assert!(changed.check_closed(&parsed).is_err());
+        let mut pomodoros = Timesheet::parse_report(&report);
+        pomodoros.add_pomodoros(NaiveDate::from_ymd(2021, 3, 2), 2);
+        assert!(!pomodoros.is_intact(&closing));
+        assert!(parsed.check_closed(&pomodoros).is_err());
+    }
+
+    #[test]
+    fn test_leave() {
+        let report = Report(
+            "# hours report v2, dates: dotted, durations: clock, language: en
+01.03.2021 01:00:00
+02.03.2021 vacation
+03.03.2021 02:00:00
+03.03.2021 sick
+Total for March 2021 03:00:00
+"
+            .to_owned(),
+        );
+        let mut timesheet = Timesheet::parse_report(&report);
+
+        assert_eq!(
+            timesheet.get_leave(NaiveDate::from_ymd(2021, 3, 2)),
+            Some(LeaveKind::Vacation)
+        );
+        assert_eq!(
+            timesheet.get_leave(NaiveDate::from_ymd(2021, 3, 3)),
+            Some(LeaveKind::Sick)
+        );
+        assert_eq!(timesheet.get_leave(NaiveDate::from_ymd(2021, 3, 1)), None);
+        assert_eq!(
+            timesheet.generate_report(&ReportFormat::default()).0,
+            report.0
+        );
+        timesheet.close(2021, 3);
+        let closed = Timesheet::parse_report(&timesheet.generate_report(&ReportFormat::default()));
+        timesheet.set_leave(NaiveDate::from_ymd(2021, 3, 2), None);
+        assert_eq!(timesheet.leave().count(), 1);
+        assert!(timesheet.check_closed(&closed).is_err());
+    }
+
+    #[test]
+    fn test_remaining_work_from() {
+        let timesheet = create_sample_timesheet();
+
+        // 05:15:01 worked before the 31st, the last working day of March 2021.
+        let work = timesheet.remaining_work_from(NaiveDate::from_ymd(2021, 3, 31));
+        assert_eq!(work.num_working_days(IncludeToday::Yes), 1);
+        assert_eq!(
+            work.time_per_day(IncludeToday::Yes),
+            Some(Duration::hours(160) - Duration::seconds(5 * 3600 + 15 * 60 + 1))
+        );
+    }
+
     fn create_sample_timesheet() -> Timesheet {
         Timesheet::parse_report(&Report(
             "
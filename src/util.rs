@@ -1,13 +1,51 @@
-use std::{env, path::PathBuf};
+use std::{
+    env,
+    fmt::Display,
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process,
+};
 
 use chrono::Duration;
 
-pub fn parse_duration(s: &str) -> Duration {
-    let mut pieces = s.split(":");
-    let hours: i64 = pieces.next().unwrap().parse().unwrap();
-    let mins: i64 = pieces.next().unwrap().parse().unwrap();
-    let secs: i64 = pieces.next().unwrap().parse().unwrap();
-    Duration::hours(hours) + Duration::minutes(mins) + Duration::seconds(secs)
+/// A directory for a test's files, removed along with them when it's dropped, even if the test
+/// fails.
+#[cfg(test)]
+pub struct TempDir(PathBuf);
+
+#[cfg(test)]
+impl TempDir {
+    /// `hours-<name>-test-<pid>` in the system's temporary directory, not created yet.
+    pub fn new(name: &str) -> Self {
+        Self(env::temp_dir().join(format!("hours-{}-test-{}", name, process::id())))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    pub fn join(&self, path: impl AsRef<Path>) -> PathBuf {
+        self.0.join(path)
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+pub fn parse_duration(s: &str) -> Option<Duration> {
+    let mut pieces = s.split(':');
+    let hours: i64 = pieces.next()?.parse().ok()?;
+    let mins: i64 = pieces.next()?.parse().ok()?;
+    let secs: i64 = pieces.next()?.parse().ok()?;
+    if pieces.next().is_some() {
+        return None;
+    }
+    Some(Duration::hours(hours) + Duration::minutes(mins) + Duration::seconds(secs))
 }
 
 pub fn format_duration(duration: Duration) -> String {
@@ -20,7 +58,52 @@ pub fn format_duration(duration: Duration) -> String {
 }
 
 pub fn home_dir() -> PathBuf {
-    PathBuf::from(env::var("HOME").unwrap())
+    match env::var_os("HOME") {
+        Some(home) => PathBuf::from(home),
+        None => exit_with_error("$HOME is not set."),
+    }
+}
+
+/// Expands a leading `~/` in paths from the settings file.
+pub fn expand_home(path: &Path) -> PathBuf {
+    match path.strip_prefix("~") {
+        Ok(rest) => home_dir().join(rest),
+        Err(_) => path.to_owned(),
+    }
+}
+
+/// Replaces the file at `path` with `content` so that, even after a crash or a full disk, it holds
+/// either the old or the new content and never a mix of both.
+pub fn write_atomic(path: &Path, content: impl AsRef<[u8]>) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if dir != Path::new("") => dir,
+        _ => Path::new("."),
+    };
+    fs::create_dir_all(dir)?;
+    let file_name = path.file_name().unwrap().to_str().unwrap();
+    let temp_path = dir.join(format!(".{}.{}.tmp", file_name, process::id()));
+    let result = (|| {
+        let mut file = File::create(&temp_path)?;
+        file.write_all(content.as_ref())?;
+        file.sync_all()?;
+        fs::rename(&temp_path, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result?;
+    sync_dir(dir)
+}
+
+/// Makes a rename in `dir` durable.
+pub fn sync_dir(dir: &Path) -> io::Result<()> {
+    File::open(dir)?.sync_all()
+}
+
+/// Prints `message` to stderr and exits, for errors the user has to fix.
+pub fn exit_with_error(message: impl Display) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
 }
 
 #[cfg(test)]
@@ -47,7 +130,26 @@ mod tests {
 
         for &(duration, string) in cases.iter() {
             assert_eq!(format_duration(duration), string);
-            assert_eq!(parse_duration(string), duration);
+            assert_eq!(parse_duration(string), Some(duration));
         }
     }
+
+    #[test]
+    fn test_parse_invalid_duration() {
+        for &string in ["", "01:00", "01:00:00:00", "01:0x:00"].iter() {
+            assert_eq!(parse_duration(string), None);
+        }
+    }
+
+    #[test]
+    fn test_write_atomic() {
+        let dir = TempDir::new("write");
+        let path = dir.join("hours.txt");
+
+        write_atomic(&path, "first").unwrap();
+        write_atomic(&path, "second").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
 }